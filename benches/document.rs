@@ -0,0 +1,51 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use yes_parser::{literal::Literal, YesDocParser};
+
+/// One section of a config-style document. Repeated to build a large file.
+const CONFIG: &str = "\
+# Window settings
+!version 1.0.2
+@min 320
+window width=320 height=240 fullscreen
+volume sfx=100 music=50
+controls id=0
+    key A 13
+    key B 14
+var msg: str=\"apple, bananas, coconut, diamond, eggplant\"";
+
+/// One element split over several lines with trailing backslashes.
+const MULTILINE: &str = "\
+frame duration=1.0s \\
+    width=10 \\
+    height=20 \\
+    origin=\"0, 0\" \\
+    flip";
+
+fn document(c: &mut Criterion) {
+    let literals = Some(vec![Literal::build_quotes()]);
+
+    let config = [CONFIG; 1000].join("\n");
+    c.bench_function("large config document", |b| {
+        b.iter(|| black_box(YesDocParser::from_string(&config, literals.clone())))
+    });
+
+    // A quote which never closes forces every later glyph to be scanned as
+    // part of the span.
+    let unbalanced = format!("x a=\"{}", "b, c=d ".repeat(2000));
+    c.bench_function("unbalanced literal line", |b| {
+        b.iter(|| black_box(YesDocParser::from_string(&unbalanced, literals.clone())))
+    });
+
+    let commas = format!("x {}", vec!["a=1"; 5000].join(", "));
+    c.bench_function("many comma line", |b| {
+        b.iter(|| black_box(YesDocParser::from_string(&commas, literals.clone())))
+    });
+
+    let multiline = [MULTILINE; 2000].join("\n");
+    c.bench_function("multiline heavy document", |b| {
+        b.iter(|| black_box(YesDocParser::from_string(&multiline, literals.clone())))
+    });
+}
+
+criterion_group!(benches, document);
+criterion_main!(benches);
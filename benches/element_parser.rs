@@ -0,0 +1,64 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use yes_parser::{
+    element_parser::ElementParser, enums::GlyphSet, literal::Literal, options::ParserOptions,
+    YesDocParser,
+};
+
+const LINES: [&str; 4] = [
+    "!version 1.0.2",
+    "window width=320 height=240 fullscreen",
+    "frame duration = 1.0s , width = 10, height=20",
+    "var msg: str=\"apple, bananas, coconut, diamond, eggplant\"",
+];
+
+fn element_parser(c: &mut Criterion) {
+    let literals = Some(vec![Literal::build_quotes()]);
+    let options = ParserOptions::default();
+
+    c.bench_function("read per line", |b| {
+        b.iter(|| {
+            for (i, line) in LINES.iter().enumerate() {
+                black_box(ElementParser::read(i, line, &literals));
+            }
+        })
+    });
+
+    c.bench_function("reset and parse per line", |b| {
+        let mut p = ElementParser::new();
+        b.iter(|| {
+            for (i, line) in LINES.iter().enumerate() {
+                p.reset(i);
+                p.parse(line, &literals, &options);
+                black_box(&p);
+            }
+        })
+    });
+}
+
+fn reserved_lookup(c: &mut Criterion) {
+    // A large file made from the sample lines. Every line is checked against
+    // [ParserOptions::reserved] for its element prefix.
+    let doc = LINES.repeat(2500).join("\n");
+    let literals = Some(vec![Literal::build_quotes()]);
+
+    c.bench_function("parse with default reserved glyphs", |b| {
+        b.iter(|| black_box(YesDocParser::from_string(&doc, literals.clone())))
+    });
+
+    let options = ParserOptions {
+        reserved: GlyphSet::RESERVED.with(b'~'),
+        ..Default::default()
+    };
+    c.bench_function("parse with custom reserved glyphs", |b| {
+        b.iter(|| {
+            black_box(YesDocParser::from_string_with_options(
+                &doc,
+                literals.clone(),
+                options.clone(),
+            ))
+        })
+    });
+}
+
+criterion_group!(benches, element_parser, reserved_lookup);
+criterion_main!(benches);
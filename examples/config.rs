@@ -1,9 +1,9 @@
 use std::{collections::HashMap, fmt};
 
 use yes_parser::{
+    document::Document,
     element::Element,
     enums::{Elements, ErrorCodes},
-    YesDocParser,
 };
 
 extern crate yes_parser;
@@ -30,9 +30,8 @@ fn main() {
 
     let result = ConfigBuilder::from_string(doc);
 
-    match &result {
-        Err(e) => println!("{}", e),
-        _ => (),
+    if let Err(e) = &result {
+        println!("{}", e);
     }
 
     assert!(result.is_ok());
@@ -43,7 +42,7 @@ fn main() {
     assert_eq!(config.version, "1.0.2");
     assert_eq!(config.window.width, 320);
     assert_eq!(config.window.height, 240);
-    assert_eq!(config.window.fullscreen, true);
+    assert!(config.window.fullscreen);
     assert_eq!(config.volume.sfx, 100.0);
     assert_eq!(config.volume.music, 50.0);
     assert_eq!(config.default_controller_idx, Some(0));
@@ -54,13 +53,13 @@ fn main() {
     assert!(controller2.is_some());
 
     let left_handed = controller1.unwrap();
-    assert_eq!(left_handed.invert_y, false);
+    assert!(!left_handed.invert_y);
     assert_eq!(*left_handed.keys.get("A").unwrap(), 13);
     assert_eq!(*left_handed.keys.get("Z").unwrap(), 1);
     assert_eq!(*left_handed.keys.get("X").unwrap(), 54);
 
     let standard = controller2.unwrap();
-    assert_eq!(standard.invert_y, true);
+    assert!(standard.invert_y);
     assert_eq!(*standard.keys.get("SPACE").unwrap(), 100);
     assert_eq!(*standard.keys.get("RIGHT").unwrap(), 101);
     assert_eq!(*standard.keys.get("LEFT").unwrap(), 213);
@@ -200,7 +199,7 @@ struct ConfigBuilder {
 
 impl ConfigBuilder {
     fn from_string(body: &str) -> Result<Config, Box<dyn std::error::Error>> {
-        let results = YesDocParser::from_string(body, None);
+        let doc = Document::from_string(body, None);
 
         let mut builder = ConfigBuilder {
             section: Sections::Unsupported,
@@ -208,15 +207,23 @@ impl ConfigBuilder {
             controller: None,
         };
 
-        for result in results {
+        // Scalar globals can be read directly from the document.
+        builder.config.version = doc
+            .global_value("version")
+            .ok_or("Version number expected!")?;
+
+        for result in doc.results {
             match result {
-                yes_parser::ParseResult::Ok { line_number, data } => {
+                yes_parser::ParseResult::Ok {
+                    line_number, data, ..
+                } => {
                     builder.process(line_number, &data)?;
                 }
                 yes_parser::ParseResult::Err {
                     line_number,
                     message,
                     code,
+                    ..
                 } => {
                     // The spec must report why it could not parse something.
                     // Generally EOL can be ignored safely depending on your
@@ -245,9 +252,9 @@ impl ConfigBuilder {
     ) -> Result<(), Box<dyn std::error::Error>> {
         match data {
             Elements::Standard { attrs, element } => {
-                self.consume_standard(line_number, &attrs, &element)
+                self.consume_standard(line_number, attrs, element)
             }
-            Elements::Global(element) => self.consume_global(line_number, &element),
+            Elements::Global(element) => self.consume_global(line_number, element),
 
             // Effectively ignores comments
             _ => Ok(()),
@@ -261,13 +268,13 @@ impl ConfigBuilder {
         element: &Element,
     ) -> Result<(), Box<dyn std::error::Error>> {
         match self.update_section(&element.text) {
-            Sections::Window => self.handle_window_field(&line_number, &element)?,
-            Sections::Volume => self.handle_volume_field(&line_number, &element)?,
-            Sections::Lang => self.handle_lang_field(&line_number, &element)?,
+            Sections::Window => self.handle_window_field(&line_number, element)?,
+            Sections::Volume => self.handle_volume_field(&line_number, element)?,
+            Sections::Lang => self.handle_lang_field(&line_number, element)?,
             // This sub-section has rules of its own. In this example, we enforce some
             // of those rules. In production, move the parsing code into its own file
             // to improve readability and make responsibility clear.
-            Sections::Controls => self.handle_controls_section(&line_number, &attrs, &element)?,
+            Sections::Controls => self.handle_controls_section(&line_number, attrs, element)?,
             Sections::Unsupported => Err(format!(
                 "#{}: Unexpected section {}!",
                 line_number, element.text
@@ -287,7 +294,7 @@ impl ConfigBuilder {
             // We could enforce positional parameters, or check to see
             // if this keyval is the value we're looking for.
             if arg.is_nameless() {
-                if arg.val == "fullscreen" {
+                if arg.val_eq_ignore_case("fullscreen") {
                     self.config.window.fullscreen = true;
                 }
                 continue;
@@ -379,7 +386,7 @@ impl ConfigBuilder {
     ) -> Result<(), Box<dyn std::error::Error>> {
         let label = element.text.as_str();
         match label {
-            "controls" => self.handle_new_controls(&line_number, &attrs, &element)?,
+            "controls" => self.handle_new_controls(line_number, attrs, element)?,
             "invert_y" => {
                 // We must have a previous entry by this element.
                 if let Some(ref mut c) = self.controller {
@@ -405,15 +412,13 @@ impl ConfigBuilder {
                         .into());
                     }
 
-                    let iter = &mut args.iter();
-                    let first = iter.nth(0).unwrap();
-                    let second = iter.nth(0).unwrap();
-
                     // Enforce position arguments.
                     // Alternatively, a reader could check for
                     // keyval names before enforcing positions.
-                    if first.is_nameless() && second.is_nameless() {
-                        c.keys.insert(first.val.clone(), second.val.parse::<u8>()?);
+                    if let (Some(action), Some(code)) =
+                        (element.get_nameless(0), element.get_nameless(1))
+                    {
+                        c.keys.insert(action.val.clone(), code.val.parse::<u8>()?);
                     } else {
                         return Err(format!(
                         "#{}: key property fields do not match expected format: `key <action> <code>`.",
@@ -492,7 +497,7 @@ impl ConfigBuilder {
         Ok(())
     }
 
-    fn update_section(&mut self, text: &String) -> Sections {
+    fn update_section(&mut self, text: &str) -> Sections {
         let next_section = Sections::from_text(text);
 
         // It's likely this is an expected element for a subsection.
@@ -523,13 +528,8 @@ impl ConfigBuilder {
         element: &Element,
     ) -> Result<(), Box<dyn std::error::Error>> {
         match element.text.as_str() {
-            "version" => {
-                if let Some(v) = element.args.first() {
-                    self.config.version = v.val.clone();
-                } else {
-                    return Err(format!("#{}: Version number expected!", line_number).into());
-                }
-            }
+            // Already read by Document::global_value.
+            "version" => {}
             _ => {
                 return Err(
                     format!("#{}: Unsupported global '{}'", line_number, element.text).into(),
@@ -0,0 +1,398 @@
+//! A compact binary encoding of parsed documents so that they can be cached
+//! and reloaded without parsing them again. Requires the `binary` feature.
+//! See [crate::document::Document::to_bytes].
+//!
+//! Every encoding begins with [MAGIC] followed by [VERSION]. Numbers are
+//! written as LEB128 varints and strings are written as their length
+//! followed by their UTF-8 bytes.
+use crate::{element::Element, enums::Elements, enums::ErrorCodes, keyval::KeyVal, ParseResult};
+
+/// The bytes every encoding begins with.
+pub const MAGIC: &[u8; 4] = b"YESB";
+
+/// The version of the encoding. This changes whenever the layout changes so
+/// that a stale cache is rejected instead of misread.
+pub const VERSION: u8 = 5;
+
+/// Appends values to an encoding which begins with [MAGIC] and [VERSION].
+pub(crate) struct Writer {
+    buf: Vec<u8>,
+}
+
+impl Writer {
+    pub(crate) fn new() -> Writer {
+        let mut buf = MAGIC.to_vec();
+        buf.push(VERSION);
+        Writer { buf }
+    }
+
+    pub(crate) fn into_bytes(self) -> Vec<u8> {
+        self.buf
+    }
+
+    pub(crate) fn usize(&mut self, mut n: usize) {
+        loop {
+            let byte = (n & 0x7f) as u8;
+            n >>= 7;
+
+            if n == 0 {
+                self.buf.push(byte);
+                return;
+            }
+
+            self.buf.push(byte | 0x80);
+        }
+    }
+
+    pub(crate) fn bool(&mut self, b: bool) {
+        self.buf.push(b as u8);
+    }
+
+    pub(crate) fn str(&mut self, s: &str) {
+        self.usize(s.len());
+        self.buf.extend_from_slice(s.as_bytes());
+    }
+
+    pub(crate) fn opt_str(&mut self, s: Option<&str>) {
+        self.bool(s.is_some());
+        if let Some(s) = s {
+            self.str(s);
+        }
+    }
+
+    pub(crate) fn element(&mut self, element: &Element) {
+        self.str(&element.text);
+
+        self.usize(element.args.len());
+        for kv in &element.args {
+            self.opt_str(kv.key.as_deref());
+            self.str(&kv.val);
+            self.opt_str(kv.literal_name());
+        }
+
+        self.usize(element.doc.len());
+        for doc in &element.doc {
+            self.str(doc);
+        }
+    }
+
+    pub(crate) fn elements(&mut self, data: &Elements) {
+        match data {
+            Elements::Standard { attrs, element } => {
+                self.buf.push(0);
+                self.usize(attrs.len());
+                for attr in attrs {
+                    self.element(attr);
+                }
+                self.element(element);
+            }
+            Elements::Attribute(element) => {
+                self.buf.push(1);
+                self.element(element);
+            }
+            Elements::Global(element) => {
+                self.buf.push(2);
+                self.element(element);
+            }
+            Elements::Comment(element) => {
+                self.buf.push(3);
+                self.element(element);
+            }
+            Elements::Blank => self.buf.push(4),
+        }
+    }
+
+    pub(crate) fn result(&mut self, result: &ParseResult) {
+        match result {
+            ParseResult::Ok {
+                line_number,
+                data,
+                depth,
+            } => {
+                self.buf.push(0);
+                self.usize(*line_number);
+                self.bool(depth.is_some());
+                if let Some(depth) = depth {
+                    self.usize(*depth);
+                }
+                self.elements(data);
+            }
+            ParseResult::Err {
+                line_number,
+                message,
+                code,
+                source,
+                first_line_number,
+                column,
+            } => {
+                self.buf.push(1);
+                self.usize(*line_number);
+                self.buf.push(code_to_byte(code));
+                self.str(message);
+                self.str(source);
+                self.usize(*first_line_number);
+                self.usize(*column);
+            }
+        }
+    }
+}
+
+/// Reads values from an encoding made by [Writer].
+pub(crate) struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    /// Returns an [Err] if [bytes] do not begin with [MAGIC] and [VERSION].
+    pub(crate) fn new(bytes: &'a [u8]) -> Result<Reader<'a>, &'static str> {
+        if !bytes.starts_with(MAGIC) {
+            return Err("Bytes are not an encoded document.");
+        }
+
+        if bytes.get(MAGIC.len()) != Some(&VERSION) {
+            return Err("Encoded document has an unsupported version.");
+        }
+
+        Ok(Reader {
+            bytes,
+            pos: MAGIC.len() + 1,
+        })
+    }
+
+    /// Returns an [Err] if there are bytes left over.
+    pub(crate) fn finish(self) -> Result<(), &'static str> {
+        match self.pos == self.bytes.len() {
+            true => Ok(()),
+            false => Err("Encoded document has trailing bytes."),
+        }
+    }
+
+    fn byte(&mut self) -> Result<u8, &'static str> {
+        let byte = *self
+            .bytes
+            .get(self.pos)
+            .ok_or("Encoded document ended unexpectedly.")?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    pub(crate) fn usize(&mut self) -> Result<usize, &'static str> {
+        let mut n: usize = 0;
+        let mut shift = 0;
+
+        loop {
+            let byte = self.byte()?;
+
+            if shift >= usize::BITS {
+                return Err("Encoded number is too large.");
+            }
+
+            n |= ((byte & 0x7f) as usize) << shift;
+            shift += 7;
+
+            if byte & 0x80 == 0 {
+                return Ok(n);
+            }
+        }
+    }
+
+    pub(crate) fn bool(&mut self) -> Result<bool, &'static str> {
+        match self.byte()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err("Encoded boolean is invalid."),
+        }
+    }
+
+    pub(crate) fn string(&mut self) -> Result<String, &'static str> {
+        let len = self.usize()?;
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or("Encoded document ended unexpectedly.")?;
+
+        let s = std::str::from_utf8(&self.bytes[self.pos..end])
+            .map_err(|_| "Encoded string is not valid UTF-8.")?;
+        self.pos = end;
+        Ok(s.to_owned())
+    }
+
+    pub(crate) fn opt_string(&mut self) -> Result<Option<String>, &'static str> {
+        match self.bool()? {
+            true => Ok(Some(self.string()?)),
+            false => Ok(None),
+        }
+    }
+
+    pub(crate) fn element(&mut self) -> Result<Element, &'static str> {
+        let mut element = Element::new(self.string()?);
+
+        for _ in 0..self.usize()? {
+            let key = self.opt_string()?;
+            let val = self.string()?;
+            let literal = self.opt_string()?;
+            element
+                .args
+                .push(KeyVal::new(key, val).with_literal_name(literal));
+        }
+
+        for _ in 0..self.usize()? {
+            element.doc.push(self.string()?);
+        }
+
+        Ok(element)
+    }
+
+    pub(crate) fn elements(&mut self) -> Result<Elements, &'static str> {
+        match self.byte()? {
+            0 => {
+                let mut attrs = Vec::new();
+                for _ in 0..self.usize()? {
+                    attrs.push(self.element()?);
+                }
+
+                Ok(Elements::Standard {
+                    attrs,
+                    element: self.element()?,
+                })
+            }
+            1 => Ok(Elements::Attribute(self.element()?)),
+            2 => Ok(Elements::Global(self.element()?)),
+            3 => Ok(Elements::Comment(self.element()?)),
+            4 => Ok(Elements::Blank),
+            _ => Err("Encoded element kind is invalid."),
+        }
+    }
+
+    pub(crate) fn result(&mut self) -> Result<ParseResult, &'static str> {
+        match self.byte()? {
+            0 => {
+                let line_number = self.usize()?;
+                let depth = match self.bool()? {
+                    true => Some(self.usize()?),
+                    false => None,
+                };
+
+                Ok(ParseResult::Ok {
+                    line_number,
+                    data: self.elements()?,
+                    depth,
+                })
+            }
+            1 => Ok(ParseResult::Err {
+                line_number: self.usize()?,
+                code: code_from_byte(self.byte()?)?,
+                message: self.string()?,
+                source: self.string()?,
+                first_line_number: self.usize()?,
+                column: self.usize()?,
+            }),
+            _ => Err("Encoded result kind is invalid."),
+        }
+    }
+}
+
+fn code_to_byte(code: &ErrorCodes) -> u8 {
+    match code {
+        ErrorCodes::BadTokenPosAttribute => 0,
+        ErrorCodes::BadTokenPosBang => 1,
+        ErrorCodes::BadTokenPosEqual => 2,
+        ErrorCodes::EolNoData => 3,
+        ErrorCodes::EolMissingElement => 4,
+        ErrorCodes::EolMissingAttribute => 5,
+        ErrorCodes::EolMissingGlobal => 6,
+        ErrorCodes::UnterminatedQuote => 7,
+        ErrorCodes::DuplicateKey => 8,
+        ErrorCodes::UnbalancedLiteral => 9,
+        ErrorCodes::LineTooLong => 10,
+        ErrorCodes::ReservedInValue => 11,
+        ErrorCodes::Runtime => 12,
+        ErrorCodes::ControlCharacter => 13,
+        ErrorCodes::TooManyArgs => 14,
+    }
+}
+
+fn code_from_byte(byte: u8) -> Result<ErrorCodes, &'static str> {
+    Ok(match byte {
+        0 => ErrorCodes::BadTokenPosAttribute,
+        1 => ErrorCodes::BadTokenPosBang,
+        2 => ErrorCodes::BadTokenPosEqual,
+        3 => ErrorCodes::EolNoData,
+        4 => ErrorCodes::EolMissingElement,
+        5 => ErrorCodes::EolMissingAttribute,
+        6 => ErrorCodes::EolMissingGlobal,
+        7 => ErrorCodes::UnterminatedQuote,
+        8 => ErrorCodes::DuplicateKey,
+        9 => ErrorCodes::UnbalancedLiteral,
+        10 => ErrorCodes::LineTooLong,
+        11 => ErrorCodes::ReservedInValue,
+        12 => ErrorCodes::Runtime,
+        13 => ErrorCodes::ControlCharacter,
+        14 => ErrorCodes::TooManyArgs,
+        _ => return Err("Encoded error code is invalid."),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        binary::{MAGIC, VERSION},
+        document::Document,
+        options::ParserOptions,
+    };
+
+    const CONTENT: &str = "!version 1.0.2
+        # Window settings
+        @resizable
+        window width=320 height=240 \"full screen\"
+        @!bad
+        controls left_handed
+            key A 13
+        @dangling
+        __END__
+        trailing text";
+
+    #[test]
+    fn roundtrip() {
+        let options = ParserOptions {
+            indent_sensitive: true,
+            doc_comments: true,
+            end_marker: Some("__END__".to_owned()),
+            ..Default::default()
+        };
+        let mut doc = Document::from_string_with_options(CONTENT, None, options);
+        doc.ignore_case = true;
+
+        let bytes = doc.to_bytes();
+        assert!(bytes.starts_with(MAGIC));
+
+        let reloaded = Document::from_bytes(&bytes).unwrap();
+        assert_eq!(reloaded.to_bytes(), bytes);
+        assert_eq!(reloaded.dump(), doc.dump());
+        assert_eq!(reloaded.trailing(), doc.trailing());
+        assert_eq!(reloaded.lint(), doc.lint());
+        assert!(reloaded.ignore_case);
+
+        // Quoting is recomputed for the reloaded keyvals.
+        let window = reloaded.find("window").unwrap();
+        assert_eq!(window.args[2].to_string(), "\"full screen\"");
+    }
+
+    #[test]
+    fn rejects_stale_or_corrupt_bytes() {
+        let bytes = Document::from_string(CONTENT, None).to_bytes();
+
+        let mut stale = bytes.clone();
+        stale[MAGIC.len()] = VERSION + 1;
+        assert!(Document::from_bytes(&stale).is_err());
+
+        assert!(Document::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(Document::from_bytes(b"not a document").is_err());
+
+        let mut extra = bytes.clone();
+        extra.push(0);
+        assert!(Document::from_bytes(&extra).is_err());
+    }
+}
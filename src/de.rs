@@ -0,0 +1,275 @@
+//! A bridge from [Element] to [serde] so that the args of an element can be
+//! deserialized directly into a user struct. Requires the `serde` feature.
+//! See [Element::try_into_typed].
+//!
+//! Every named [crate::keyval::KeyVal] maps to the struct field with the
+//! same name as its key. Values are parsed into the type of the field, so
+//! `width=320` fills a `width: u32` field. Every nameless keyval is collected
+//! in order into one sequence under the key [NAMELESS_KEY], which can be
+//! renamed onto any field with `#[serde(rename = "nameless")]`.
+use std::fmt;
+
+use serde::{
+    de::{self, value::MapDeserializer, value::SeqDeserializer, IntoDeserializer, Visitor},
+    forward_to_deserialize_any,
+};
+
+use crate::element::Element;
+
+/// The key which nameless args are collected under.
+pub const NAMELESS_KEY: &str = "nameless";
+
+/// The [Error] returned when an [Element] could not be deserialized.
+#[derive(Debug, PartialEq)]
+pub struct Error(String);
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error(msg.to_string())
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Returns a [de::Deserializer] over the args of [element].
+pub fn from_element(element: &Element) -> impl de::Deserializer<'_, Error = Error> {
+    let mut entries: Vec<(&str, ArgValue)> = element
+        .args
+        .iter()
+        .filter_map(|kv| Some((kv.key.as_deref()?, ArgValue::One(&kv.val))))
+        .collect();
+
+    let nameless: Vec<&str> = element
+        .args
+        .iter()
+        .filter(|kv| kv.is_nameless())
+        .map(|kv| kv.val.as_str())
+        .collect();
+
+    if !nameless.is_empty() {
+        entries.push((NAMELESS_KEY, ArgValue::Many(nameless)));
+    }
+
+    MapDeserializer::new(entries.into_iter())
+}
+
+/// The value of one entry in the map of args.
+enum ArgValue<'a> {
+    One(&'a str),
+    Many(Vec<&'a str>),
+}
+
+impl<'de> IntoDeserializer<'de, Error> for ArgValue<'de> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+/// Implements each `deserialize_*` method of [ArgValue] by passing a single
+/// value to the same method of [ValueDeserializer].
+macro_rules! deserialize_one {
+    ($($method:ident),* $(,)?) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                match self {
+                    ArgValue::One(s) => ValueDeserializer(s).$method(visitor),
+                    ArgValue::Many(_) => self.deserialize_any(visitor),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for ArgValue<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            ArgValue::One(s) => ValueDeserializer(s).deserialize_any(visitor),
+            ArgValue::Many(list) => visitor.visit_seq(SeqDeserializer::new(
+                list.into_iter().map(ValueDeserializer),
+            )),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self {
+            ArgValue::One(s) => ValueDeserializer(s).deserialize_enum(name, variants, visitor),
+            ArgValue::Many(_) => self.deserialize_any(visitor),
+        }
+    }
+
+    deserialize_one! {
+        deserialize_bool, deserialize_i8, deserialize_i16, deserialize_i32,
+        deserialize_i64, deserialize_i128, deserialize_u8, deserialize_u16,
+        deserialize_u32, deserialize_u64, deserialize_u128, deserialize_f32,
+        deserialize_f64, deserialize_char,
+    }
+
+    forward_to_deserialize_any! {
+        str string bytes byte_buf unit unit_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+/// Deserializes one value by parsing it into the requested type.
+struct ValueDeserializer<'a>(&'a str);
+
+impl<'de> IntoDeserializer<'de, Error> for ValueDeserializer<'de> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+/// Implements each `deserialize_*` method by parsing the value with
+/// [str::parse] and passing it to the matching `visit_*` method.
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident),* $(,)?) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                match self.0.parse() {
+                    Ok(v) => visitor.$visit(v),
+                    Err(_) => Err(de::Error::invalid_value(de::Unexpected::Str(self.0), &visitor)),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_borrowed_str(self.0)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_enum(IntoDeserializer::<Error>::into_deserializer(self.0))
+    }
+
+    deserialize_parsed! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+        deserialize_char => visit_char,
+    }
+
+    forward_to_deserialize_any! {
+        str string bytes byte_buf unit unit_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use crate::{enums::Elements, YesDocParser};
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct WindowConfig {
+        width: u32,
+        height: u32,
+        title: Option<String>,
+
+        #[serde(rename = "nameless", default)]
+        flags: Vec<String>,
+    }
+
+    #[test]
+    fn try_into_typed() {
+        let results = YesDocParser::from_string(
+            "window width=320 height=240\nwindow fullscreen width=640 height=480 vsync",
+            None,
+        );
+
+        let windows: Vec<WindowConfig> = results
+            .iter()
+            .map(|result| match result.data() {
+                Some(Elements::Standard { element, .. }) => element.try_into_typed().unwrap(),
+                _ => panic!("Standard element expected!"),
+            })
+            .collect();
+
+        assert_eq!(
+            windows[0],
+            WindowConfig {
+                width: 320,
+                height: 240,
+                title: None,
+                flags: Vec::new(),
+            }
+        );
+
+        assert_eq!(windows[1].width, 640);
+        assert_eq!(windows[1].flags, vec!["fullscreen", "vsync"]);
+    }
+
+    #[test]
+    fn try_into_typed_error() {
+        let results = YesDocParser::from_string("window width=wide height=240", None);
+
+        let err = match results[0].data() {
+            Some(Elements::Standard { element, .. }) => {
+                element.try_into_typed::<WindowConfig>().unwrap_err()
+            }
+            _ => panic!("Standard element expected!"),
+        };
+
+        assert!(err.to_string().contains("wide"));
+    }
+}
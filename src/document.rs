@@ -0,0 +1,2156 @@
+use std::{cmp::Ordering, collections::HashMap, fs::File, io, str::FromStr};
+
+use crate::{
+    element::Element,
+    enums::{Delimiters, Elements, ErrorCodes, Glyphs},
+    keyval::KeyVal,
+    lint::{Diagnostic, Lint, LintOptions},
+    literal::Literal,
+    options::{FormatOptions, ParserOptions},
+    visitor::Visitor,
+    ParseError, ParseResult, YesDocParser,
+};
+
+/// [Change] describes one difference between two [Document]s.
+/// See [Document::diff].
+#[derive(Debug, PartialEq)]
+pub enum Change {
+    /// An element named [Change::Added::name] exists only in the new document.
+    Added { name: String },
+
+    /// An element named [Change::Removed::name] exists only in the old document.
+    Removed { name: String },
+
+    /// An element named [Change::Modified::name] exists in both documents,
+    /// but the [crate::keyval::KeyVal] identified by [Change::Modified::key]
+    /// was added, removed, or has a different value. If [Change::Modified::key]
+    /// is [None], then the nameless keyvals of the element differ.
+    Modified { name: String, key: Option<String> },
+}
+
+/// [MergeStrategy] decides what [Document::merge_with] does with an element
+/// of the overlay which has no match in the base document.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum MergeStrategy {
+    /// The element is added to the base document. Globals are added after the
+    /// other globals and standard elements are added to the end.
+    Append,
+
+    /// The merge fails with a [ParseError] and the base document is unchanged.
+    Reject,
+}
+
+/// Maps line numbers to the results of a [Document] in source order, even
+/// though [YesDocParser] hoists globals to the front of the results.
+/// See [Document::line_index].
+pub struct LineIndex<'a> {
+    /// Every result paired with its line number, sorted by line number.
+    entries: Vec<(usize, &'a ParseResult)>,
+}
+
+impl<'a> LineIndex<'a> {
+    /// Returns the result which was read from [line], if any. Lookups are
+    /// a binary search.
+    pub fn get(&self, line: usize) -> Option<&'a ParseResult> {
+        let i = self.entries.binary_search_by_key(&line, |(n, _)| *n).ok()?;
+        Some(self.entries[i].1)
+    }
+
+    /// Returns the number of indexed results.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if there are no indexed results.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// One line of output from [Document::format].
+enum Row<'a> {
+    /// A line which is written as-is, such as a comment or an error.
+    Text(String),
+
+    /// An element with its prefix [Glyphs] which is written by the formatter.
+    Element(Glyphs, &'a Element),
+}
+
+/// A parsed YES document. This is a thin wrapper around the list of
+/// [ParseResult] values returned by [YesDocParser] which provides
+/// document-wide queries.
+pub struct Document {
+    pub results: Vec<ParseResult>,
+
+    /// When true, element names are compared ignoring ASCII case by
+    /// [Document::find] and [Document::group_by_name] so that `Window`
+    /// matches `window`. Defaults to false.
+    pub ignore_case: bool,
+
+    /// The content after [ParserOptions::end_marker], if any.
+    trailing: Option<String>,
+
+    /// Attributes which were not followed by a standard element, paired with
+    /// the line number they were declared on.
+    dangling: Vec<(usize, Element)>,
+
+    /// Line numbers whose args were dropped by
+    /// [ParserOptions::max_args_per_line].
+    truncated: Vec<usize>,
+
+    /// Line numbers whose misplaced prefix was read as part of the name by
+    /// [ParserOptions::recover_prefixes].
+    recovered: Vec<usize>,
+}
+
+impl Document {
+    /// Constructs a new [Document] from a list of [ParseResult] values.
+    pub fn new(results: Vec<ParseResult>) -> Document {
+        Document {
+            results,
+            ignore_case: false,
+            trailing: None,
+            dangling: Vec::new(),
+            truncated: Vec::new(),
+            recovered: Vec::new(),
+        }
+    }
+
+    /// Returns a [Document] read from an input [file], or the first
+    /// [io::Error] if a line cannot be read. See [YesDocParser::from_file].
+    pub fn from_file(file: &File, literals: Option<Vec<Literal>>) -> Result<Document, io::Error> {
+        Document::from_file_with_options(file, literals, ParserOptions::default())
+    }
+
+    /// A variation of [Document::from_file] which parses with [options].
+    /// See [YesDocParser::from_file_with_options].
+    pub fn from_file_with_options(
+        file: &File,
+        literals: Option<Vec<Literal>>,
+        options: ParserOptions,
+    ) -> Result<Document, io::Error> {
+        Ok(Document::from_parser(YesDocParser::read_file(
+            file, literals, options,
+        )?))
+    }
+
+    /// Returns a [Document] read from [body].
+    /// See [YesDocParser::from_string].
+    pub fn from_string(body: &str, literals: Option<Vec<Literal>>) -> Document {
+        Document::from_string_with_options(body, literals, ParserOptions::default())
+    }
+
+    /// A variation of [Document::from_string] which parses with [options].
+    /// See [YesDocParser::from_string_with_options].
+    pub fn from_string_with_options(
+        body: &str,
+        literals: Option<Vec<Literal>>,
+        options: ParserOptions,
+    ) -> Document {
+        Document::from_parser(YesDocParser::read_string(body, literals, options))
+    }
+
+    /// Constructs a new [Document] from the results of a finished [parser].
+    fn from_parser(parser: YesDocParser) -> Document {
+        let mut doc = Document::new(parser.results);
+        doc.trailing = parser.trailing;
+        doc.dangling = parser.attrs;
+        doc.truncated = parser.truncated;
+        doc.recovered = parser.recovered;
+        doc
+    }
+
+    /// Encodes the document into a compact binary form which can be cached and
+    /// reloaded with [Document::from_bytes] without parsing it again.
+    /// Requires the `binary` feature. See [crate::binary].
+    #[cfg(feature = "binary")]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = crate::binary::Writer::new();
+
+        writer.bool(self.ignore_case);
+        writer.opt_str(self.trailing.as_deref());
+
+        writer.usize(self.dangling.len());
+        for (line_number, attr) in &self.dangling {
+            writer.usize(*line_number);
+            writer.element(attr);
+        }
+
+        writer.usize(self.truncated.len());
+        for line_number in &self.truncated {
+            writer.usize(*line_number);
+        }
+
+        writer.usize(self.recovered.len());
+        for line_number in &self.recovered {
+            writer.usize(*line_number);
+        }
+
+        writer.usize(self.results.len());
+        for result in &self.results {
+            writer.result(result);
+        }
+
+        writer.into_bytes()
+    }
+
+    /// Decodes a [Document] encoded by [Document::to_bytes]. Returns an [Err]
+    /// if [bytes] were encoded by a different [crate::binary::VERSION] or are
+    /// not a valid encoding. Requires the `binary` feature.
+    #[cfg(feature = "binary")]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Document, &'static str> {
+        let mut reader = crate::binary::Reader::new(bytes)?;
+
+        let ignore_case = reader.bool()?;
+        let trailing = reader.opt_string()?;
+
+        let mut dangling = Vec::new();
+        for _ in 0..reader.usize()? {
+            dangling.push((reader.usize()?, reader.element()?));
+        }
+
+        let mut truncated = Vec::new();
+        for _ in 0..reader.usize()? {
+            truncated.push(reader.usize()?);
+        }
+
+        let mut recovered = Vec::new();
+        for _ in 0..reader.usize()? {
+            recovered.push(reader.usize()?);
+        }
+
+        let mut results = Vec::new();
+        for _ in 0..reader.usize()? {
+            results.push(reader.result()?);
+        }
+
+        reader.finish()?;
+
+        Ok(Document {
+            results,
+            ignore_case,
+            trailing,
+            dangling,
+            truncated,
+            recovered,
+        })
+    }
+
+    /// Returns the content after [ParserOptions::end_marker] verbatim if the
+    /// marker was found. Otherwise [None].
+    pub fn trailing(&self) -> Option<&str> {
+        self.trailing.as_deref()
+    }
+
+    /// Returns an iterator over every successfully parsed [Elements] value.
+    /// Results which are [ParseResult::Err] are skipped.
+    pub fn elements(&self) -> impl Iterator<Item = &Elements> {
+        self.results.iter().filter_map(|result| match result {
+            ParseResult::Ok { data, .. } => Some(data),
+            ParseResult::Err { .. } => None,
+        })
+    }
+
+    /// Calls [f] on every successfully parsed [Elements] value in place, in
+    /// the order of [Document::results], e.g. to rename every element before
+    /// [Document::format]. Results which are [ParseResult::Err] are left
+    /// untouched. Attributes are passed as part of their standard element.
+    /// Attributes which were not applied to any element are not passed.
+    pub fn apply(&mut self, mut f: impl FnMut(&mut Elements)) {
+        for result in &mut self.results {
+            if let ParseResult::Ok { data, .. } = result {
+                f(data);
+            }
+        }
+    }
+
+    /// Sorts [Document::results] with the comparator [cmp]. The sort is
+    /// stable, so results which compare equal keep their current order.
+    /// Globals are sorted like any other result. Call [Document::hoist_globals]
+    /// afterwards to move them back to the front.
+    pub fn sort_by(&mut self, cmp: impl FnMut(&ParseResult, &ParseResult) -> Ordering) {
+        self.results.sort_by(cmp);
+    }
+
+    /// Moves every [Elements::Global] result to the front of
+    /// [Document::results] as [YesDocParser] does after parsing. The relative
+    /// order of the globals and of the remaining results is kept.
+    pub fn hoist_globals(&mut self) {
+        YesDocParser::hoist_globals(&mut self.results);
+    }
+
+    /// Appends [data] to the end of [Document::results]. Pushed elements were
+    /// never read from a line, so each is numbered one past the largest line
+    /// number in the document, starting from `1` for an empty document. Its
+    /// depth is [None]. Globals are not hoisted, see [Document::hoist_globals].
+    pub fn push(&mut self, data: Elements) {
+        let line_number = self.next_line_number();
+        self.push_numbered(line_number, data);
+    }
+
+    /// Returns one past the largest line number in [Document::results], or
+    /// `1` for an empty document. See [Document::push].
+    fn next_line_number(&self) -> usize {
+        self.results
+            .iter()
+            .map(ParseResult::line_number)
+            .max()
+            .unwrap_or(0)
+            + 1
+    }
+
+    /// Appends [data] to the end of [Document::results] as [line_number].
+    fn push_numbered(&mut self, line_number: usize, data: Elements) {
+        self.results.push(ParseResult::Ok {
+            line_number,
+            data,
+            depth: None,
+        });
+    }
+
+    /// Consumes [self] and partitions the results by outcome without cloning.
+    /// Successful results are paired with their line number.
+    pub fn into_parts(self) -> (Vec<(usize, Elements)>, Vec<ParseError>) {
+        let mut oks = Vec::new();
+        let mut errs = Vec::new();
+
+        for result in self.results {
+            match result {
+                ParseResult::Ok {
+                    line_number, data, ..
+                } => oks.push((line_number, data)),
+                ParseResult::Err {
+                    line_number,
+                    message,
+                    code,
+                    source,
+                    first_line_number,
+                    column,
+                } => errs.push(ParseError {
+                    line_number,
+                    message,
+                    code,
+                    source,
+                    first_line_number,
+                    column,
+                }),
+            }
+        }
+
+        (oks, errs)
+    }
+
+    /// Consumes [self] and returns only the successful results.
+    /// See [Document::into_parts].
+    pub fn into_oks(self) -> Vec<(usize, Elements)> {
+        self.into_parts().0
+    }
+
+    /// Consumes [self] and returns only the errors.
+    /// See [Document::into_parts].
+    pub fn into_errors(self) -> Vec<ParseError> {
+        self.into_parts().1
+    }
+
+    /// Walks every result in order and dispatches it to the matching method of
+    /// [visitor]. Globals are hoisted so they are visited first. The attributes
+    /// of a [Elements::Standard] element are visited before the element itself.
+    pub fn accept(&self, visitor: &mut impl Visitor) {
+        for result in &self.results {
+            match result {
+                ParseResult::Ok {
+                    line_number, data, ..
+                } => match data {
+                    Elements::Standard { attrs, element } => {
+                        for attr in attrs {
+                            visitor.visit_attribute(*line_number, attr);
+                        }
+                        visitor.visit_standard(*line_number, element, attrs);
+                    }
+                    Elements::Attribute(element) => visitor.visit_attribute(*line_number, element),
+                    Elements::Global(element) => visitor.visit_global(*line_number, element),
+                    Elements::Comment(element) => visitor.visit_comment(*line_number, element),
+                    Elements::Blank => visitor.visit_blank(*line_number),
+                },
+                ParseResult::Err {
+                    line_number,
+                    message,
+                    code,
+                    ..
+                } => visitor.visit_error(*line_number, code, message),
+            }
+        }
+    }
+
+    /// Returns an iterator over the [Element] of every [Elements::Global]
+    /// value. Globals are hoisted so these are the first elements of the
+    /// document. See [YesDocParser::parse_globals_first] to choose how the
+    /// rest of a document is parsed from its globals.
+    pub fn globals(&self) -> impl Iterator<Item = &Element> {
+        self.elements().filter_map(|data| match data {
+            Elements::Global(element) => Some(element),
+            _ => None,
+        })
+    }
+
+    /// Returns an iterator over the [Element] of every [Elements::Standard]
+    /// value. Their attributes are not yielded, see [Document::attributes].
+    pub fn standards(&self) -> impl Iterator<Item = &Element> {
+        self.elements().filter_map(|data| match data {
+            Elements::Standard { element, .. } => Some(element),
+            _ => None,
+        })
+    }
+
+    /// Returns an iterator over every attribute applied to a standard element,
+    /// in the order of the elements and then of their attributes. Attributes
+    /// which were not followed by a standard element are not yielded.
+    pub fn attributes(&self) -> impl Iterator<Item = &Element> {
+        self.elements().flat_map(|data| match data {
+            Elements::Standard { attrs, .. } => attrs.as_slice(),
+            _ => &[],
+        })
+    }
+
+    /// Returns an iterator over the [Element] of every [Elements::Comment]
+    /// value. The comment text is [Element::text].
+    pub fn comments(&self) -> impl Iterator<Item = &Element> {
+        self.elements().filter_map(|data| match data {
+            Elements::Comment(element) => Some(element),
+            _ => None,
+        })
+    }
+
+    /// Returns the value of the first arg of the global named [name] parsed
+    /// as [T], e.g. `1.5` from `!scale 1.5`. A global with more than one arg
+    /// such as `!scale 1.5 2` only reads the first. Returns [None] if there
+    /// is no such global, it has no args, or the value does not parse.
+    /// See [Document::ignore_case].
+    pub fn global_value<T: FromStr>(&self, name: &str) -> Option<T> {
+        self.globals()
+            .find(|element| self.name_eq(&element.text, name))?
+            .args
+            .first()?
+            .val
+            .parse()
+            .ok()
+    }
+
+    /// Returns the first [Elements::Standard] or [Elements::Global] element
+    /// whose name is [name]. See [Document::ignore_case].
+    pub fn find(&self, name: &str) -> Option<&Element> {
+        self.named_elements()
+            .find(|element| self.name_eq(&element.text, name))
+    }
+
+    /// Returns every [Elements::Standard] or [Elements::Global] element whose
+    /// name is [name] in document order. See [Document::ignore_case].
+    pub fn find_all(&self, name: &str) -> Vec<&Element> {
+        self.named_elements()
+            .filter(|element| self.name_eq(&element.text, name))
+            .collect()
+    }
+
+    /// Returns every [Elements::Standard] element which carries an attribute
+    /// named [attr_name], such as `@default`, in document order. Sticky
+    /// attributes count as well. See [Document::ignore_case].
+    pub fn elements_with_attr(&self, attr_name: &str) -> Vec<&Element> {
+        self.elements()
+            .filter_map(|data| match data {
+                Elements::Standard { attrs, element }
+                    if attrs.iter().any(|attr| self.name_eq(&attr.text, attr_name)) =>
+                {
+                    Some(element)
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the result which was read from [line], if any. Attributes and
+    /// doc comments have no result of their own. A multiline element is
+    /// found by the line it ends on. See [ParseResult::line_number].
+    ///
+    /// This scans every result. Build a [LineIndex] with
+    /// [Document::line_index] to look up many lines.
+    pub fn element_at_line(&self, line: usize) -> Option<&ParseResult> {
+        self.results
+            .iter()
+            .find(|result| result.line_number() == line)
+    }
+
+    /// Returns a [LineIndex] over the results of this document by their
+    /// original line number. The index borrows the document, so it cannot
+    /// go stale.
+    pub fn line_index(&self) -> LineIndex<'_> {
+        let mut entries: Vec<(usize, &ParseResult)> = self
+            .results
+            .iter()
+            .map(|result| (result.line_number(), result))
+            .collect();
+
+        entries.sort_by_key(|(line, _)| *line);
+        LineIndex { entries }
+    }
+
+    /// Returns the comments directly above the standard element read from
+    /// [element_line], top to bottom and trimmed, for tools such as
+    /// documentation generators.
+    ///
+    /// Comments belong to the element when they are on the lines directly
+    /// above it. Attribute lines between them and the element are skipped. A
+    /// blank line, an error, or any other element breaks the association. The
+    /// spec has no trailing comments since a `#` after the element name is
+    /// part of its args.
+    ///
+    /// If the document was parsed with [ParserOptions::doc_comments], then
+    /// the comments were already attached and [Element::doc] is returned.
+    /// Returns an empty list if there is no standard element on that line.
+    pub fn doc_comments_for(&self, element_line: usize) -> Vec<&str> {
+        let index = self.line_index();
+
+        let element = match index.get(element_line).and_then(ParseResult::data) {
+            Some(Elements::Standard { element, .. }) => element,
+            _ => return Vec::new(),
+        };
+
+        if !element.doc.is_empty() {
+            return element.doc.iter().map(|doc| doc.trim()).collect();
+        }
+
+        let mut comments = Vec::new();
+        for line in (1..element_line).rev() {
+            match index.get(line).map(ParseResult::data) {
+                // Applied attributes have no result of their own.
+                None => continue,
+                Some(Some(Elements::Comment(comment))) => comments.push(comment.text.trim()),
+                _ => break,
+            }
+        }
+
+        comments.reverse();
+        comments
+    }
+
+    /// Replaces the first [Elements::Standard] or [Elements::Global] element
+    /// whose name is [name] with [new] and returns true. Other elements with
+    /// the same name are left unchanged. The line number and depth of the
+    /// replaced result are kept. If no element matches, then this returns
+    /// false. See [Document::ignore_case].
+    pub fn replace_element(&mut self, name: &str, new: Elements) -> bool {
+        let ignore_case = self.ignore_case;
+
+        for result in &mut self.results {
+            let data = match result {
+                ParseResult::Ok { data, .. } => data,
+                ParseResult::Err { .. } => continue,
+            };
+
+            let text = match data {
+                Elements::Standard { element, .. } | Elements::Global(element) => &element.text,
+                _ => continue,
+            };
+
+            let matches = match ignore_case {
+                true => text.eq_ignore_ascii_case(name),
+                false => text == name,
+            };
+
+            if matches {
+                *data = new;
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Groups every [Elements::Standard] and [Elements::Global] element by
+    /// name. Each group is in document order. If [Document::ignore_case] is
+    /// true, then the names are folded to ASCII lowercase.
+    pub fn group_by_name(&self) -> HashMap<String, Vec<&Element>> {
+        let mut groups: HashMap<String, Vec<&Element>> = HashMap::new();
+
+        for element in self.named_elements() {
+            let name = match self.ignore_case {
+                true => element.text.to_ascii_lowercase(),
+                false => element.text.clone(),
+            };
+
+            groups.entry(name).or_default().push(element);
+        }
+
+        groups
+    }
+
+    /// Returns an iterator over the [Element] of every [Elements::Standard]
+    /// and [Elements::Global] value. These are the elements which have names.
+    fn named_elements(&self) -> impl Iterator<Item = &Element> {
+        self.elements().filter_map(|data| match data {
+            Elements::Standard { element, .. } => Some(element),
+            Elements::Global(element) => Some(element),
+            _ => None,
+        })
+    }
+
+    /// Runs [Element::validate_with] with [f] on every [Elements::Standard]
+    /// and [Elements::Global] element and returns each failure with the line
+    /// number of its element, in document order.
+    pub fn validate_with<F>(&self, f: F) -> Vec<ParseError>
+    where
+        F: Fn(&Element) -> Result<(), String>,
+    {
+        self.results
+            .iter()
+            .filter_map(|result| match result {
+                ParseResult::Ok {
+                    line_number,
+                    data: Elements::Standard { element, .. } | Elements::Global(element),
+                    ..
+                } => element.validate_with(&f).err().map(|mut err| {
+                    err.line_number = *line_number;
+                    err.first_line_number = *line_number;
+                    err
+                }),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns every [Diagnostic] found by the checks in [LintOptions].
+    /// See [Document::lint_with] to toggle the checks.
+    pub fn lint(&self) -> Vec<Diagnostic> {
+        self.lint_with(&LintOptions::default())
+    }
+
+    /// A variation of [Document::lint] which only runs the checks enabled in
+    /// [options]. The diagnostics are ordered by line number.
+    pub fn lint_with(&self, options: &LintOptions) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        let mut globals: Vec<(usize, &Element)> = Vec::new();
+
+        for result in &self.results {
+            let (line_number, element, is_global) = match result {
+                ParseResult::Ok {
+                    line_number,
+                    data: Elements::Standard { element, .. },
+                    ..
+                } => (*line_number, element, false),
+                ParseResult::Ok {
+                    line_number,
+                    data: Elements::Global(element),
+                    ..
+                } => (*line_number, element, true),
+                _ => continue,
+            };
+
+            if options.empty_elements && element.text.is_empty() {
+                diagnostics.push(Diagnostic {
+                    line_number,
+                    severity: Lint::EmptyElement.severity(),
+                    lint: Lint::EmptyElement,
+                    message: "Element has an empty name.".to_owned(),
+                });
+            }
+
+            if !is_global {
+                continue;
+            }
+
+            if options.duplicate_globals {
+                if let Some((first, _)) = globals
+                    .iter()
+                    .find(|(_, other)| self.name_eq(&other.text, &element.text))
+                {
+                    diagnostics.push(Diagnostic {
+                        line_number,
+                        severity: Lint::DuplicateGlobal.severity(),
+                        lint: Lint::DuplicateGlobal,
+                        message: format!(
+                            "Global `{}` was already declared on line {}.",
+                            element.text, first
+                        ),
+                    });
+                }
+            }
+
+            globals.push((line_number, element));
+        }
+
+        if options.dangling_attributes {
+            for (line_number, attr) in &self.dangling {
+                diagnostics.push(Diagnostic {
+                    line_number: *line_number,
+                    severity: Lint::DanglingAttribute.severity(),
+                    lint: Lint::DanglingAttribute,
+                    message: format!("Attribute `{}` is not applied to any element.", attr.text),
+                });
+            }
+        }
+
+        if options.truncated_args {
+            for line_number in &self.truncated {
+                diagnostics.push(Diagnostic {
+                    line_number: *line_number,
+                    severity: Lint::TruncatedArgs.severity(),
+                    lint: Lint::TruncatedArgs,
+                    message: "Line has too many args and the rest were dropped.".to_owned(),
+                });
+            }
+        }
+
+        if options.recovered_prefixes {
+            for line_number in &self.recovered {
+                diagnostics.push(Diagnostic {
+                    line_number: *line_number,
+                    severity: Lint::RecoveredPrefix.severity(),
+                    lint: Lint::RecoveredPrefix,
+                    message: "Misplaced element prefix was read as part of the name.".to_owned(),
+                });
+            }
+        }
+
+        diagnostics.sort_by_key(|d| d.line_number);
+        diagnostics
+    }
+
+    /// Renders the document back into normalized YES source with consistent
+    /// spacing. Attributes and doc comments are written directly above the
+    /// element they apply to. Lines which could not be parsed, including blank
+    /// lines, are kept as their original source. See [FormatOptions].
+    ///
+    /// Formatting the parse of a formatted document returns the same source.
+    pub fn format(&self, options: FormatOptions) -> String {
+        Document::format_results(&self.results, options)
+    }
+
+    /// Renders [results] as [Document::format] does. Used by
+    /// [YesDocParser::to_yes_string] for results without a [Document].
+    pub(crate) fn format_results(results: &[ParseResult], options: FormatOptions) -> String {
+        let mut rows: Vec<(usize, Row)> = Vec::new();
+        let mut depths: Vec<usize> = Vec::new();
+
+        for result in results {
+            let (data, depth) = match result {
+                ParseResult::Ok { data, depth, .. } => (data, *depth),
+                ParseResult::Err { source, .. } => {
+                    rows.push((0, Row::Text(Document::continue_lines(source.trim()))));
+                    continue;
+                }
+            };
+
+            // Each deeper indentation opens a new level until a shallower
+            // indentation closes it.
+            let level = match depth {
+                None => 0,
+                Some(d) => {
+                    while depths.last().is_some_and(|&top| top > d) {
+                        depths.pop();
+                    }
+
+                    if depths.last() != Some(&d) {
+                        depths.push(d);
+                    }
+
+                    depths.len() - 1
+                }
+            };
+
+            let (glyph, element, attrs) = match data {
+                Elements::Standard { attrs, element } => (Glyphs::None, element, attrs.as_slice()),
+                Elements::Attribute(element) => (Glyphs::At, element, [].as_slice()),
+                Elements::Global(element) => (Glyphs::Bang, element, [].as_slice()),
+                Elements::Comment(element) => {
+                    let text = Document::continue_lines(&element.text);
+                    rows.push((level, Row::Text(format!("#{}", text))));
+                    continue;
+                }
+                Elements::Blank => {
+                    rows.push((0, Row::Text(String::new())));
+                    continue;
+                }
+            };
+
+            for doc in &element.doc {
+                let doc = Document::continue_lines(doc);
+                rows.push((level, Row::Text(format!("#{}", doc))));
+            }
+
+            for attr in attrs {
+                rows.push((level, Row::Element(Glyphs::At, attr)));
+            }
+
+            rows.push((level, Row::Element(glyph, element)));
+        }
+
+        let delimiter = match (options.align_equals, &options.delimiter) {
+            (true, _) | (false, Delimiters::Comma) => ", ",
+            _ => " ",
+        };
+
+        let mut lines: Vec<String> = Vec::new();
+        let mut i = 0;
+        while i < rows.len() {
+            let (level, ref row) = rows[i];
+            let indent = " ".repeat(level * options.indent);
+
+            let (glyph, element) = match row {
+                Row::Text(text) if text.is_empty() => {
+                    lines.push(String::new());
+                    i += 1;
+                    continue;
+                }
+                Row::Text(text) => {
+                    lines.push(format!("{}{}", indent, text));
+                    i += 1;
+                    continue;
+                }
+                Row::Element(glyph, element) => (glyph, element),
+            };
+
+            if !options.align_equals || *glyph != Glyphs::None {
+                lines.push(Document::format_element(&indent, glyph, element, delimiter));
+                i += 1;
+                continue;
+            }
+
+            // Collect the run of standard elements at this level.
+            let mut run: Vec<&Element> = Vec::new();
+            while let Some((l, Row::Element(Glyphs::None, e))) = rows.get(i) {
+                if *l != level {
+                    break;
+                }
+                run.push(e);
+                i += 1;
+            }
+
+            lines.extend(Document::format_aligned(&indent, &run));
+        }
+
+        // Trailing blank lines are not kept so that formatting is stable.
+        while lines.last().is_some_and(|line| line.is_empty()) {
+            lines.pop();
+        }
+
+        let mut source = lines.join("\n");
+        source.push('\n');
+        source
+    }
+
+    /// Renders every result as an indented tree for debugging how each line
+    /// was interpreted. Each result begins with its line number and kind,
+    /// followed by its doc comments, attributes, and keyvals. A keyval is
+    /// marked `(quoted)` if it must be quoted to be written back as source.
+    ///
+    /// ```text
+    /// 2 standard `window`
+    ///   arg key="width" val="320"
+    /// 7 standard `controls`
+    ///   attr `default`
+    ///   arg val="left_handed"
+    /// ```
+    pub fn dump(&self) -> String {
+        let mut lines = Vec::new();
+
+        for result in &self.results {
+            let (line_number, data, depth) = match result {
+                ParseResult::Ok {
+                    line_number,
+                    data,
+                    depth,
+                } => (line_number, data, depth),
+                ParseResult::Err {
+                    line_number,
+                    code,
+                    source,
+                    ..
+                } => {
+                    lines.push(format!(
+                        "{} error {:?} source={:?}",
+                        line_number, code, source
+                    ));
+                    continue;
+                }
+            };
+
+            let (kind, element, attrs) = match data {
+                Elements::Standard { attrs, element } => ("standard", element, attrs.as_slice()),
+                Elements::Attribute(element) => ("attribute", element, &[][..]),
+                Elements::Global(element) => ("global", element, &[][..]),
+                Elements::Comment(element) => {
+                    lines.push(format!("{} comment {:?}", line_number, element.text));
+                    continue;
+                }
+                Elements::Blank => {
+                    lines.push(format!("{} blank", line_number));
+                    continue;
+                }
+            };
+
+            let mut line = format!("{} {} `{}`", line_number, kind, element.text);
+            if let Some(depth) = depth {
+                line += &format!(" depth={}", depth);
+            }
+            lines.push(line);
+
+            for doc in &element.doc {
+                lines.push(format!("  doc {:?}", doc));
+            }
+
+            for attr in attrs {
+                lines.push(format!("  attr `{}`", attr.text));
+                Document::dump_args("    ", attr, &mut lines);
+            }
+
+            Document::dump_args("  ", element, &mut lines);
+        }
+
+        let mut dump = lines.join("\n");
+        dump.push('\n');
+        dump
+    }
+
+    /// Renders each keyval of [element] on its own line for [Document::dump].
+    fn dump_args(indent: &str, element: &Element, lines: &mut Vec<String>) {
+        for kv in &element.args {
+            let mut line = format!("{}arg", indent);
+
+            if let Some(ref key) = kv.key {
+                line += &format!(" key={:?}", key);
+            }
+
+            line += &format!(" val={:?}", kv.val);
+
+            if kv.to_string() != kv.display_raw() {
+                line += " (quoted)";
+            }
+
+            lines.push(line);
+        }
+    }
+
+    /// Renders one [element] on one line with its prefix [glyph].
+    fn format_element(indent: &str, glyph: &Glyphs, element: &Element, delimiter: &str) -> String {
+        let mut line = String::from(indent);
+
+        if *glyph != Glyphs::None {
+            line.push(glyph.value() as char);
+        }
+
+        line += &Document::format_name(&element.text);
+
+        if !element.args.is_empty() {
+            let args: Vec<String> = element.args.iter().map(|kv| kv.to_string()).collect();
+            line.push(' ');
+            line += &args.join(delimiter);
+        }
+
+        line
+    }
+
+    /// Returns [text] with a [Glyphs::Backslash] before each line break, so
+    /// that a line break kept by [ParserOptions::quoted_newlines] is written
+    /// as the continuation it was read from.
+    fn continue_lines(text: &str) -> String {
+        text.replace('\n', "\\\n")
+    }
+
+    /// Returns the element name [text] as it must be written to be parsed
+    /// back unchanged. Names are unquoted when parsed, so a name which is
+    /// empty or itself surrounded by quotes is quoted again. A name which
+    /// begins with an element prefix glyph or with whitespace is quoted so
+    /// that it is not read as another kind of element or trimmed.
+    fn format_name(text: &str) -> String {
+        use crate::utils::StringUtils;
+
+        let mut name = text.to_owned();
+        let prefixed = name.bytes().next().is_some_and(|c| {
+            c == Glyphs::At.value() || c == Glyphs::Bang.value() || c == Glyphs::Hash.value()
+        });
+
+        let padded = name.starts_with(char::is_whitespace) || name.ends_with(char::is_whitespace);
+
+        if name.is_empty()
+            || prefixed
+            || padded
+            || (name.len() > 1 && StringUtils::is_quoted(&name))
+        {
+            name.insert(0, '"');
+            name.push('"');
+        }
+        name
+    }
+
+    /// Renders a [run] of standard elements with their names and keyvals
+    /// padded into columns. See [FormatOptions::align_equals].
+    fn format_aligned(indent: &str, run: &[&Element]) -> Vec<String> {
+        // Keys and values are quoted the same way as [KeyVal]'s display.
+        let quote = |s: &str| KeyVal::new(None, s.to_owned()).to_string();
+        let width = |s: &str| s.chars().count();
+
+        let names: Vec<String> = run.iter().map(|e| Document::format_name(&e.text)).collect();
+        let name_width = names.iter().map(|n| width(n)).max().unwrap_or(0);
+        let columns = run.iter().map(|e| e.args.len()).max().unwrap_or(0);
+
+        let mut key_widths = vec![0; columns];
+        for element in run {
+            for (col, kv) in element.args.iter().enumerate() {
+                if let Some(ref key) = kv.key {
+                    key_widths[col] = key_widths[col].max(width(&quote(key)));
+                }
+            }
+        }
+
+        let cells: Vec<Vec<String>> = run
+            .iter()
+            .map(|element| {
+                element
+                    .args
+                    .iter()
+                    .enumerate()
+                    .map(|(col, kv)| match kv.key {
+                        Some(ref key) => format!(
+                            "{:<w$} = {}",
+                            quote(key),
+                            quote(&kv.val),
+                            w = key_widths[col]
+                        ),
+                        None => quote(&kv.val),
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let mut cell_widths = vec![0; columns];
+        for row in &cells {
+            for (col, cell) in row.iter().enumerate() {
+                cell_widths[col] = cell_widths[col].max(width(cell));
+            }
+        }
+
+        names
+            .iter()
+            .zip(cells)
+            .map(|(name, row)| {
+                let last = row.len().saturating_sub(1);
+                let args: Vec<String> = row
+                    .into_iter()
+                    .enumerate()
+                    .map(|(col, cell)| match col == last {
+                        true => cell,
+                        false => format!("{:<w$}", cell + ",", w = cell_widths[col] + 1),
+                    })
+                    .collect();
+
+                let line = format!("{}{:<w$} {}", indent, name, args.join(" "), w = name_width);
+
+                line.trim_end().to_owned()
+            })
+            .collect()
+    }
+
+    /// Layers [overlay] on top of [self], such as user settings over defaults.
+    ///
+    /// Elements are keyed by kind and name, respecting [Document::ignore_case].
+    /// The second element with the same name in [overlay] matches the second
+    /// element with that name in [self], and so on. For each match:
+    ///
+    /// - A standard element is merged arg-wise. Named keyvals of the overlay
+    ///   replace or are added to the base, and the nth nameless keyval of the
+    ///   overlay replaces the nth nameless keyval of the base. Attributes of the
+    ///   overlay which the base element does not have are added.
+    /// - A global is replaced by the overlay's global.
+    ///
+    /// Elements of [overlay] without a match are handled by [strategy].
+    /// Comments and errors of [overlay] are ignored.
+    pub fn merge_with(
+        &mut self,
+        overlay: &Document,
+        strategy: MergeStrategy,
+    ) -> Result<(), ParseError> {
+        let mut indices: HashMap<(bool, String), Vec<usize>> = HashMap::new();
+        for (i, result) in self.results.iter().enumerate() {
+            if let Some((_, is_global, element)) = Document::named_result(result) {
+                indices
+                    .entry((is_global, self.fold_name(&element.text)))
+                    .or_default()
+                    .push(i);
+            }
+        }
+
+        let mut counts: HashMap<(bool, String), usize> = HashMap::new();
+        let mut plan = Vec::new();
+
+        for result in &overlay.results {
+            let (line_number, is_global, element) = match Document::named_result(result) {
+                Some(named) => named,
+                None => continue,
+            };
+
+            let id = (is_global, self.fold_name(&element.text));
+            let nth = counts.entry(id.clone()).or_default();
+            let target = indices.get(&id).and_then(|list| list.get(*nth)).copied();
+
+            *nth += 1;
+
+            if target.is_none() && strategy == MergeStrategy::Reject {
+                return Err(ParseError {
+                    line_number,
+                    message: format!("Element `{}` is not in the base document.", element.text),
+                    code: ErrorCodes::Runtime,
+                    source: String::new(),
+                    first_line_number: line_number,
+                    column: 0,
+                });
+            }
+
+            plan.push((target, result));
+        }
+
+        // Matched elements are merged first so that their indices stay valid.
+        let mut unmatched = Vec::new();
+
+        for (target, result) in plan {
+            let target = match target {
+                Some(target) => target,
+                None => {
+                    unmatched.push(result);
+                    continue;
+                }
+            };
+
+            let base = match &mut self.results[target] {
+                ParseResult::Ok { data, .. } => data,
+                ParseResult::Err { .. } => continue,
+            };
+
+            match (base, result.data()) {
+                (Elements::Global(base), Some(Elements::Global(element))) => {
+                    let doc = std::mem::take(&mut base.doc);
+                    *base = Elements::copy(element);
+                    if base.doc.is_empty() {
+                        base.doc = doc;
+                    }
+                }
+                (
+                    Elements::Standard {
+                        attrs,
+                        element: base,
+                    },
+                    Some(Elements::Standard {
+                        attrs: overlay_attrs,
+                        element,
+                    }),
+                ) => {
+                    for attr in overlay_attrs {
+                        if !attrs.iter().any(|a| a.text == attr.text) {
+                            attrs.push(Elements::copy(attr));
+                        }
+                    }
+
+                    Document::merge_args(base, element);
+                }
+                _ => {}
+            }
+        }
+
+        for result in unmatched {
+            let (line_number, depth, data) = match result {
+                ParseResult::Ok {
+                    line_number,
+                    depth,
+                    data,
+                } => (*line_number, *depth, Document::copy_data(data)),
+                ParseResult::Err { .. } => continue,
+            };
+
+            let index = match data {
+                Elements::Global(_) => self
+                    .results
+                    .iter()
+                    .position(|r| !matches!(r.data(), Some(Elements::Global(_))))
+                    .unwrap_or(self.results.len()),
+                _ => self.results.len(),
+            };
+
+            self.results.insert(
+                index,
+                ParseResult::Ok {
+                    line_number,
+                    data,
+                    depth,
+                },
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Merges the keyvals of [overlay] into [base]. See [Document::merge_with].
+    fn merge_args(base: &mut Element, overlay: &Element) {
+        let mut nth = 0;
+
+        for kv in &overlay.args {
+            if !kv.is_nameless() {
+                base.upsert(KeyVal::copy(kv));
+                continue;
+            }
+
+            match base
+                .args
+                .iter_mut()
+                .filter(|arg| arg.is_nameless())
+                .nth(nth)
+            {
+                Some(arg) => *arg = KeyVal::copy(kv),
+                None => base.args.push(KeyVal::copy(kv)),
+            }
+
+            nth += 1;
+        }
+    }
+
+    /// Returns the line number, whether or not it is global, and the [Element]
+    /// of a successful [Elements::Standard] or [Elements::Global] [result].
+    fn named_result(result: &ParseResult) -> Option<(usize, bool, &Element)> {
+        match result {
+            ParseResult::Ok {
+                line_number,
+                data: Elements::Global(element),
+                ..
+            } => Some((*line_number, true, element)),
+            ParseResult::Ok {
+                line_number,
+                data: Elements::Standard { element, .. },
+                ..
+            } => Some((*line_number, false, element)),
+            _ => None,
+        }
+    }
+
+    /// Returns a deep copy of [data] including the attributes.
+    fn copy_data(data: &Elements) -> Elements {
+        match data {
+            Elements::Standard { attrs, element } => Elements::Standard {
+                attrs: attrs.iter().map(Elements::copy).collect(),
+                element: Elements::copy(element),
+            },
+            Elements::Attribute(element) => Elements::Attribute(Elements::copy(element)),
+            Elements::Global(element) => Elements::Global(Elements::copy(element)),
+            Elements::Comment(element) => Elements::Comment(Elements::copy(element)),
+            Elements::Blank => Elements::Blank,
+        }
+    }
+
+    /// Returns [name] folded to ASCII lowercase if [Document::ignore_case].
+    fn fold_name(&self, name: &str) -> String {
+        match self.ignore_case {
+            true => name.to_ascii_lowercase(),
+            false => name.to_owned(),
+        }
+    }
+
+    /// Compares two element names respecting [Document::ignore_case].
+    fn name_eq(&self, a: &str, b: &str) -> bool {
+        match self.ignore_case {
+            true => a.eq_ignore_ascii_case(b),
+            false => a == b,
+        }
+    }
+
+    /// Compares [self], the old document, against [other], the new document,
+    /// and returns the list of [Change]s between them.
+    ///
+    /// [Elements::Global] elements are compared by name and
+    /// [Elements::Standard] elements are compared by name and position. The
+    /// second element with the same name in one document is compared with the
+    /// second element with the same name in the other document, and so on.
+    /// Comments and attributes are not compared.
+    ///
+    /// Removals and modifications are listed in the order of [self] followed by
+    /// additions in the order of [other].
+    pub fn diff(&self, other: &Document) -> Vec<Change> {
+        self.diff_by(other, |element| element.text.clone())
+    }
+
+    /// A variation of [Document::diff] which identifies [Elements::Standard]
+    /// elements by the result of [key] instead of by their name. Elements
+    /// which produce the same key are compared by position.
+    pub fn diff_by<F>(&self, other: &Document, key: F) -> Vec<Change>
+    where
+        F: Fn(&Element) -> String,
+    {
+        let old = self.keyed_elements(&key);
+        let new = other.keyed_elements(&key);
+        let old_ids: HashMap<&(bool, String, usize), &Element> =
+            old.iter().map(|(id, element)| (id, *element)).collect();
+        let new_ids: HashMap<&(bool, String, usize), &Element> =
+            new.iter().map(|(id, element)| (id, *element)).collect();
+        let mut changes = Vec::new();
+
+        for (id, a) in &old {
+            match new_ids.get(id) {
+                None => changes.push(Change::Removed {
+                    name: a.text.clone(),
+                }),
+                Some(b) => Document::diff_args(a, b, &mut changes),
+            }
+        }
+
+        for (id, b) in &new {
+            if !old_ids.contains_key(id) {
+                changes.push(Change::Added {
+                    name: b.text.clone(),
+                });
+            }
+        }
+
+        changes
+    }
+
+    /// Pairs every global and standard [Element] with an identity used to
+    /// match it against another document. The identity is whether or not the
+    /// element is global, the key produced by [key] (the name for globals),
+    /// and how many elements with this same key came before it.
+    fn keyed_elements<F>(&self, key: &F) -> Vec<((bool, String, usize), &Element)>
+    where
+        F: Fn(&Element) -> String,
+    {
+        let mut list: Vec<((bool, String, usize), &Element)> = Vec::new();
+        let mut counts: HashMap<(bool, String), usize> = HashMap::new();
+
+        for data in self.elements() {
+            let (is_global, k, element) = match data {
+                Elements::Global(element) => (true, element.text.clone(), element),
+                Elements::Standard { element, .. } => (false, key(element), element),
+                _ => continue,
+            };
+
+            let count = counts.entry((is_global, k.clone())).or_insert(0);
+            let nth = *count;
+            *count += 1;
+
+            list.push(((is_global, k, nth), element));
+        }
+
+        list
+    }
+
+    /// Appends a [Change::Modified] to [changes] for every named key whose
+    /// value differs between [a] and [b], and one more if the nameless
+    /// keyvals differ.
+    fn diff_args(a: &Element, b: &Element, changes: &mut Vec<Change>) {
+        let mut keys: Vec<&String> = Vec::new();
+        for kv in a.args.iter().chain(b.args.iter()) {
+            if let Some(ref k) = kv.key {
+                if !keys.contains(&k) {
+                    keys.push(k);
+                }
+            }
+        }
+
+        for k in keys {
+            let find = |element: &'_ Element| {
+                element
+                    .args
+                    .iter()
+                    .find(|kv| kv.key.as_ref() == Some(k))
+                    .map(|kv| kv.val.clone())
+            };
+
+            if find(a) != find(b) {
+                changes.push(Change::Modified {
+                    name: a.text.clone(),
+                    key: Some(k.clone()),
+                });
+            }
+        }
+
+        let nameless = |element: &'_ Element| {
+            element
+                .args
+                .iter()
+                .filter(|kv| kv.is_nameless())
+                .map(|kv| kv.val.clone())
+                .collect::<Vec<String>>()
+        };
+
+        if nameless(a) != nameless(b) {
+            changes.push(Change::Modified {
+                name: a.text.clone(),
+                key: None,
+            });
+        }
+    }
+}
+
+/// Pushes every element in order with [Document::push].
+impl Extend<Elements> for Document {
+    /// The same as calling [Document::push] for each element, but the next
+    /// line number is only searched for once.
+    fn extend<T: IntoIterator<Item = Elements>>(&mut self, iter: T) {
+        let first = self.next_line_number();
+
+        for (line_number, data) in (first..).zip(iter) {
+            self.push_numbered(line_number, data);
+        }
+    }
+}
+
+impl From<Vec<ParseResult>> for Document {
+    fn from(results: Vec<ParseResult>) -> Self {
+        Document::new(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        document::{Change, Document, MergeStrategy},
+        element::Element,
+        enums::ErrorCodes,
+        enums::{Delimiters, Elements},
+        keyval::KeyVal,
+        lint::{Lint, LintOptions, Severity},
+        options::{FormatOptions, ParserOptions},
+        visitor::Visitor,
+        ParseResult,
+    };
+
+    const CONFIG_V1: &str = "!version 1.0.2
+        window width=320 height=240 fullscreen
+        volume sfx=100 music=50
+        lang en
+
+        @default
+        controls left_handed
+            key A 13
+            key Z 1
+            # etc...";
+
+    const CONFIG_V2: &str = "!version 1.0.3
+        window width=640 height=240
+        volume sfx=100 music=50
+        !theme dark
+
+        @default
+        controls left_handed
+            key A 13
+            key Z 2
+            key X 54
+            # etc...";
+
+    #[test]
+    fn find_ignore_case() {
+        let mut doc = Document::from_string(CONFIG_V1, None);
+
+        assert!(doc.find("window").is_some());
+        assert!(doc.find("Window").is_none());
+        assert!(!doc.group_by_name().contains_key("Window"));
+
+        doc.ignore_case = true;
+
+        let window = doc.find("Window").expect("Expected to find window.");
+        assert_eq!(window.text, "window");
+        assert_eq!(window.get_key_value::<u16>("width"), Some(320));
+
+        let groups = doc.group_by_name();
+        assert_eq!(groups.get("key").unwrap().len(), 2);
+        assert_eq!(groups.get("version").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn find_all() {
+        let content = format!(
+            "{}\n        controls standard\n            key A 1",
+            CONFIG_V1
+        );
+        let doc = Document::from_string(&content, None);
+
+        let controls: Vec<&str> = doc
+            .find_all("controls")
+            .iter()
+            .map(|element| element.args[0].val.as_str())
+            .collect();
+        assert_eq!(controls, vec!["left_handed", "standard"]);
+
+        assert_eq!(doc.find_all("key").len(), 3);
+        assert!(doc.find_all("missing").is_empty());
+    }
+
+    #[test]
+    fn line_index() {
+        let doc = Document::from_string(CONFIG_V2, None);
+        let index = doc.line_index();
+
+        // Globals are hoisted but still found by their own line.
+        let name = |result: Option<&ParseResult>| match result.and_then(ParseResult::data) {
+            Some(Elements::Standard { element, .. }) | Some(Elements::Global(element)) => {
+                element.text.clone()
+            }
+            _ => panic!("Named element expected!"),
+        };
+
+        assert_eq!(name(index.get(4)), "theme");
+        assert_eq!(name(index.get(7)), "controls");
+        assert_eq!(name(index.get(9)), "key");
+        assert_eq!(name(doc.element_at_line(9)), "key");
+        assert_eq!(name(doc.element_at_line(1)), "version");
+
+        // The attribute on line 6 belongs to the element on line 7.
+        assert!(index.get(6).is_none());
+        assert!(index.get(100).is_none());
+        assert_eq!(index.len(), doc.results.len());
+    }
+
+    #[test]
+    fn globals() {
+        let doc = Document::from_string(CONFIG_V2, None);
+
+        let names: Vec<&str> = doc.globals().map(|g| g.text.as_str()).collect();
+        assert_eq!(names, vec!["version", "theme"]);
+    }
+
+    /// Counts each kind of element and records the order they were visited.
+    #[derive(Default)]
+    struct CountingVisitor {
+        order: String,
+        standards: usize,
+        attributes: usize,
+        globals: usize,
+        comments: usize,
+        errors: usize,
+    }
+
+    impl Visitor for CountingVisitor {
+        fn visit_standard(&mut self, _: usize, _: &Element, _: &[Element]) {
+            self.order.push('s');
+            self.standards += 1;
+        }
+
+        fn visit_attribute(&mut self, _: usize, _: &Element) {
+            self.order.push('a');
+            self.attributes += 1;
+        }
+
+        fn visit_global(&mut self, _: usize, _: &Element) {
+            self.order.push('g');
+            self.globals += 1;
+        }
+
+        fn visit_comment(&mut self, _: usize, _: &Element) {
+            self.order.push('c');
+            self.comments += 1;
+        }
+
+        fn visit_error(&mut self, _: usize, _: &ErrorCodes, _: &str) {
+            self.order.push('e');
+            self.errors += 1;
+        }
+    }
+
+    #[test]
+    fn accept_visitor() {
+        let doc = Document::from_string(&format!("{}\n!theme dark", CONFIG_V1), None);
+
+        let mut visitor = CountingVisitor::default();
+        doc.accept(&mut visitor);
+
+        // Globals are hoisted and attributes come before their element.
+        // The blank line in the fixture is reported as an error.
+        assert_eq!(visitor.order, "ggssseasssc");
+        assert_eq!(visitor.standards, 6);
+        assert_eq!(visitor.attributes, 1);
+        assert_eq!(visitor.globals, 2);
+        assert_eq!(visitor.comments, 1);
+        assert_eq!(visitor.errors, 1);
+    }
+
+    #[test]
+    fn end_marker() {
+        let content = "!version 1\nwindow width=320\n__END__\nraw # data\n\n  more = data";
+        let options = ParserOptions {
+            end_marker: Some("__END__".to_owned()),
+            ..Default::default()
+        };
+
+        let doc = Document::from_string_with_options(content, None, options);
+        assert_eq!(doc.results.len(), 2);
+        assert_eq!(doc.trailing(), Some("raw # data\n\n  more = data"));
+
+        // Without the option, the marker is parsed as an element.
+        let doc = Document::from_string(content, None);
+        assert!(doc.find("__END__").is_some());
+        assert_eq!(doc.trailing(), None);
+    }
+
+    #[test]
+    fn end_marker_crlf() {
+        let content = "window \\\r\nwidth=320\r\n__END__\r\ntrail";
+        let options = ParserOptions {
+            end_marker: Some("__END__".to_owned()),
+            ..Default::default()
+        };
+
+        let doc = Document::from_string_with_options(content, None, options.clone());
+        assert_eq!(doc.trailing(), Some("trail"));
+        let window = doc.find("window").unwrap();
+        assert_eq!(window.get_key_value::<u16>("width"), Some(320));
+
+        let path = std::env::temp_dir().join("yes_parser_end_marker_crlf.mesh");
+        std::fs::write(&path, content).unwrap();
+        let file = std::fs::File::open(&path).unwrap();
+        let from_file = Document::from_file_with_options(&file, None, options).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(from_file.trailing(), Some("trail"));
+        assert!(from_file.find("window").is_some());
+    }
+
+    #[test]
+    fn lint() {
+        let content = "!version 1.0\nwindow width=320\n!version 2.0\n\"\"\n@unused";
+        let doc = Document::from_string(content, None);
+
+        let lints: Vec<(usize, Lint)> = doc
+            .lint()
+            .into_iter()
+            .map(|d| (d.line_number, d.lint))
+            .collect();
+
+        assert_eq!(
+            lints,
+            vec![
+                (3, Lint::DuplicateGlobal),
+                (4, Lint::EmptyElement),
+                (5, Lint::DanglingAttribute),
+            ]
+        );
+
+        let options = LintOptions {
+            duplicate_globals: false,
+            ..Default::default()
+        };
+        assert!(doc
+            .lint_with(&options)
+            .iter()
+            .all(|d| d.lint != Lint::DuplicateGlobal));
+
+        // The fixture has no smells.
+        assert!(Document::from_string(CONFIG_V1, None).lint().is_empty());
+    }
+
+    #[test]
+    fn format_idempotent() {
+        let parser_options = ParserOptions {
+            indent_sensitive: true,
+            ..Default::default()
+        };
+
+        for options in [
+            FormatOptions::default(),
+            FormatOptions {
+                indent: 2,
+                delimiter: Delimiters::Comma,
+                ..Default::default()
+            },
+            FormatOptions {
+                align_equals: true,
+                ..Default::default()
+            },
+        ] {
+            let once = Document::from_string_with_options(CONFIG_V1, None, parser_options.clone())
+                .format(options.clone());
+            let twice = Document::from_string_with_options(&once, None, parser_options.clone())
+                .format(options);
+
+            assert_eq!(once, twice);
+        }
+    }
+
+    #[test]
+    fn format_align_equals() {
+        let doc = Document::from_string(
+            "volume sfx=100 music=50\nwindow w=320 height=240 full",
+            None,
+        );
+
+        let options = FormatOptions {
+            align_equals: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            doc.format(options),
+            "volume sfx = 100, music  = 50\n\
+             window w   = 320, height = 240, full\n"
+        );
+
+        assert_eq!(
+            doc.format(FormatOptions::default()),
+            "volume sfx=100 music=50\nwindow w=320 height=240 full\n"
+        );
+    }
+
+    #[test]
+    fn format_quotes_names() {
+        // Each name would otherwise be read back as something else. Names end
+        // at the first space or tab, so they cannot be padded by either.
+        let doc = Document::from_string("\"\" a\n\"#x\" b\n\"\"\"\"", None);
+
+        assert_eq!(
+            doc.format(FormatOptions::default()),
+            "\"\" a\n\"#x\" b\n\"\"\"\"\n"
+        );
+    }
+
+    #[test]
+    fn format_quoted_newlines() {
+        let options = ParserOptions {
+            quoted_newlines: true,
+            ..Default::default()
+        };
+        let source = "text v=\"line1 \\\nline2\" w=1\n# \"a \\\nb\"\n";
+
+        let doc = Document::from_string_with_options(source, None, options.clone());
+        let formatted = doc.format(FormatOptions::default());
+        assert_eq!(formatted, source);
+
+        let doc = Document::from_string_with_options(&formatted, None, options);
+        assert_eq!(doc.standards().count(), 1);
+        assert_eq!(doc.comments().count(), 1);
+        assert!(!doc.results.iter().any(|result| matches!(
+            result,
+            ParseResult::Err {
+                code: ErrorCodes::UnterminatedQuote,
+                ..
+            }
+        )));
+    }
+
+    #[test]
+    fn diff_config() {
+        let old = Document::from_string(CONFIG_V1, None);
+        let new = Document::from_string(CONFIG_V2, None);
+
+        let changes = old.diff(&new);
+
+        assert_eq!(
+            changes,
+            vec![
+                Change::Modified {
+                    name: "version".to_owned(),
+                    key: None,
+                },
+                Change::Modified {
+                    name: "window".to_owned(),
+                    key: Some("width".to_owned()),
+                },
+                Change::Modified {
+                    name: "window".to_owned(),
+                    key: None,
+                },
+                Change::Removed {
+                    name: "lang".to_owned(),
+                },
+                Change::Modified {
+                    name: "key".to_owned(),
+                    key: None,
+                },
+                Change::Added {
+                    name: "theme".to_owned(),
+                },
+                Change::Added {
+                    name: "key".to_owned(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_same_document() {
+        let old = Document::from_string(CONFIG_V1, None);
+        let new = Document::from_string(CONFIG_V1, None);
+
+        assert!(old.diff(&new).is_empty());
+    }
+
+    #[test]
+    fn diff_by_key() {
+        let old = Document::from_string("key A 13\nkey Z 1", None);
+        let new = Document::from_string("key Z 1\nkey A 13", None);
+
+        // By position, both keys appear to have changed.
+        assert_eq!(old.diff(&new).len(), 2);
+
+        // By the first nameless value, nothing has changed.
+        let changes = old.diff_by(&new, |element| {
+            element
+                .args
+                .first()
+                .map(|kv| kv.val.clone())
+                .unwrap_or_default()
+        });
+        assert!(changes.is_empty());
+    }
+
+    #[test]
+    fn into_parts() {
+        let doc = Document::from_string("!version 1.0\n@!bad\nwindow width=320\n@!worse", None);
+        let total = doc.results.len();
+
+        // [doc] is moved here and can no longer be used.
+        let (oks, errs) = doc.into_parts();
+
+        assert_eq!(oks.len(), 2);
+        assert_eq!(errs.len(), 2);
+        assert_eq!(oks.len() + errs.len(), total);
+        assert_eq!(oks[1].0, 3);
+        assert_eq!(errs[1].line_number, 4);
+        assert!(errs.iter().all(|err| err.code != ErrorCodes::Runtime));
+
+        let doc = Document::from_string("!version 1.0\n@!bad", None);
+        assert_eq!(doc.into_oks().len(), 1);
+
+        let doc = Document::from_string("!version 1.0\n@!bad", None);
+        assert_eq!(doc.into_errors()[0].line_number, 2);
+    }
+
+    #[test]
+    fn dump() {
+        let dump = Document::from_string(CONFIG_V1, None).dump();
+        let lines: Vec<&str> = dump.lines().collect();
+
+        assert_eq!(lines[0], "1 global `version`");
+        assert_eq!(lines[1], "  arg val=\"1.0.2\"");
+        assert!(dump.contains(
+            "2 standard `window`\n  \
+            arg key=\"width\" val=\"320\"\n  \
+            arg key=\"height\" val=\"240\"\n  \
+            arg val=\"fullscreen\"\n"
+        ));
+        assert!(dump.contains("5 error EolNoData source=\"\"\n"));
+        assert!(
+            dump.contains("7 standard `controls`\n  attr `default`\n  arg val=\"left_handed\"\n")
+        );
+        assert!(dump.contains("10 comment \" etc...\"\n"));
+
+        let dump = Document::from_string("x \"a b\"=c", None).dump();
+        assert!(dump.contains("  arg key=\"a b\" val=\"c\" (quoted)"));
+    }
+
+    #[test]
+    fn validate_with() {
+        let doc = Document::from_string(CONFIG_V1, None);
+
+        let errors = doc.validate_with(|element| match element.text.as_str() {
+            "key" if element.args.len() != 2 => Err("expected a key and a code".to_owned()),
+            "volume" if !element.has_key("voice") => Err("missing voice".to_owned()),
+            _ => Ok(()),
+        });
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].line_number, 3);
+        assert_eq!(errors[0].message, "volume: missing voice");
+    }
+
+    #[test]
+    fn merge_with() {
+        let overlay = Document::from_string(
+            "!version 2.0
+            !theme dark
+            window width=640 borderless
+            @@locked
+            controls right_handed
+                key Z 2
+                key X 5",
+            None,
+        );
+
+        let mut doc = Document::from_string(CONFIG_V1, None);
+        assert!(doc.merge_with(&overlay, MergeStrategy::Reject).is_err());
+        assert_eq!(
+            doc.format(FormatOptions::default()),
+            Document::from_string(CONFIG_V1, None).format(FormatOptions::default())
+        );
+
+        doc.merge_with(&overlay, MergeStrategy::Append).unwrap();
+
+        let globals: Vec<String> = doc.globals().map(|g| g.to_string()).collect();
+        assert_eq!(globals, vec!["version args={2.0}", "theme args={dark}"]);
+
+        let window = doc.find("window").unwrap();
+        assert_eq!(window.get_key_value::<u16>("width"), Some(640));
+        assert_eq!(window.get_key_value::<u16>("height"), Some(240));
+        assert_eq!(window.first_nameless(), Some("borderless"));
+
+        // Untouched elements keep their defaults.
+        assert_eq!(
+            doc.find("volume").unwrap().get_key_value::<u8>("sfx"),
+            Some(100)
+        );
+
+        let controls = doc
+            .elements()
+            .find_map(|data| match data {
+                Elements::Standard { attrs, element } if element.text == "controls" => {
+                    Some((attrs, element))
+                }
+                _ => None,
+            })
+            .unwrap();
+        let attrs: Vec<&str> = controls.0.iter().map(|a| a.text.as_str()).collect();
+        assert_eq!(attrs, vec!["default", "locked"]);
+        assert_eq!(controls.1.first_nameless(), Some("right_handed"));
+
+        let keys: Vec<String> = doc.find_all("key").iter().map(|k| k.to_string()).collect();
+        assert_eq!(keys, vec!["key args={Z, 2}", "key args={X, 5}"]);
+    }
+
+    #[test]
+    fn replace_element() {
+        let mut doc = Document::from_string(CONFIG_V1, None);
+
+        let window = Elements::standard_from(
+            "window",
+            &[(Some("width"), "1920"), (Some("height"), "1080")],
+        );
+        assert!(doc.replace_element("window", window));
+        assert!(!doc.replace_element("missing", Elements::new_standard("x".to_owned())));
+
+        let window = doc.find("window").unwrap();
+        assert_eq!(window.get_key_value::<u16>("width"), Some(1920));
+        assert!(doc.results.iter().any(|r| r.line_number() == 2
+            && matches!(r.data(), Some(Elements::Standard { element, .. }) if element.text == "window")));
+
+        let source = doc.format(FormatOptions::default());
+        assert!(source.contains("\nwindow width=1920 height=1080\n"));
+        assert!(!source.contains("320"));
+
+        // Only the first element with the name is replaced.
+        assert!(doc.replace_element("key", Elements::standard_from("key", &[(None, "B")])));
+        let keys: Vec<String> = doc.find_all("key").iter().map(|k| k.to_string()).collect();
+        assert_eq!(keys, vec!["key args={B}", "key args={Z, 1}"]);
+    }
+
+    #[test]
+    fn global_value() {
+        let doc = Document::from_string("!version 1.0.2\n!scale 1.5 2\n!empty\nx", None);
+
+        assert_eq!(
+            doc.global_value::<String>("version"),
+            Some("1.0.2".to_owned())
+        );
+        assert_eq!(doc.global_value::<f32>("scale"), Some(1.5));
+
+        // Standard elements, missing args, and bad values are skipped.
+        assert_eq!(doc.global_value::<String>("x"), None);
+        assert_eq!(doc.global_value::<String>("empty"), None);
+        assert_eq!(doc.global_value::<f32>("version"), None);
+        assert_eq!(doc.global_value::<f32>("missing"), None);
+    }
+
+    #[test]
+    fn elements_with_attr() {
+        let mut doc = Document::from_string(CONFIG_V1, None);
+
+        let defaults: Vec<String> = doc
+            .elements_with_attr("default")
+            .iter()
+            .map(|element| element.to_string())
+            .collect();
+        assert_eq!(defaults, vec!["controls args={left_handed}"]);
+
+        assert!(doc.elements_with_attr("Default").is_empty());
+        assert!(doc.elements_with_attr("missing").is_empty());
+
+        doc.ignore_case = true;
+        assert_eq!(doc.elements_with_attr("Default").len(), 1);
+    }
+
+    #[test]
+    fn doc_comments_for() {
+        let content = "# Settings file\n\
+            \n\
+            # The main window.\n\
+            #   Sizes are in pixels.\n\
+            @resizable\n\
+            window width=320 height=240\n\
+            # Not for window.\n\
+            \n\
+            volume sfx=100\n\
+            # Audio language.\n\
+            !lang en\n\
+            # Controls\n\
+            controls left_handed";
+        let doc = Document::from_string(content, None);
+
+        let window = doc.find("window").unwrap().to_string();
+        assert_eq!(window, "window args={width=320, height=240}");
+
+        assert_eq!(
+            doc.doc_comments_for(6),
+            vec!["The main window.", "Sizes are in pixels."]
+        );
+
+        // A blank line or another element breaks the association.
+        assert!(doc.doc_comments_for(9).is_empty());
+        assert_eq!(doc.doc_comments_for(13), vec!["Controls"]);
+
+        // Only standard elements have doc comments.
+        assert!(doc.doc_comments_for(1).is_empty());
+        assert!(doc.doc_comments_for(11).is_empty());
+        assert!(doc.doc_comments_for(99).is_empty());
+
+        // Attached doc comments are returned as well.
+        let options = ParserOptions {
+            doc_comments: true,
+            ..Default::default()
+        };
+        let doc = Document::from_string_with_options(content, None, options);
+        assert_eq!(
+            doc.doc_comments_for(6),
+            vec!["The main window.", "Sizes are in pixels."]
+        );
+    }
+
+    #[test]
+    fn apply() {
+        let mut doc = Document::from_string(&format!("{}\n@!bad", CONFIG_V1), None);
+
+        doc.apply(|data| match data {
+            Elements::Standard { attrs, element } => {
+                element.text = element.text.to_uppercase();
+                for attr in attrs {
+                    attr.text = attr.text.to_uppercase();
+                }
+            }
+            Elements::Global(element) => element.text = element.text.to_uppercase(),
+            _ => {}
+        });
+
+        let names: Vec<&str> = doc
+            .named_elements()
+            .map(|element| element.text.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            vec!["VERSION", "WINDOW", "VOLUME", "LANG", "CONTROLS", "KEY", "KEY"]
+        );
+
+        let controls = doc.elements_with_attr("DEFAULT");
+        assert_eq!(controls[0].text, "CONTROLS");
+
+        // Comments and errors are unchanged.
+        assert!(doc.format(FormatOptions::default()).contains("# etc..."));
+        assert_eq!(doc.results.last().unwrap().line_number(), 11);
+        assert!(doc.results.last().unwrap().is_err());
+    }
+
+    #[test]
+    fn sort_by() {
+        let mut doc = Document::from_string(&format!("{}\n!assert ok", CONFIG_V1), None);
+
+        let name = |result: &ParseResult| match result.data() {
+            Some(Elements::Standard { element, .. })
+            | Some(Elements::Global(element))
+            | Some(Elements::Comment(element)) => element.text.clone(),
+            _ => String::new(),
+        };
+        let names = |doc: &Document| -> Vec<String> { doc.results.iter().map(name).collect() };
+
+        doc.sort_by(|a, b| name(a).cmp(&name(b)));
+        assert_eq!(
+            names(&doc),
+            vec![
+                "", " etc...", "assert", "controls", "key", "key", "lang", "version", "volume",
+                "window"
+            ]
+        );
+
+        // Hoisting keeps the sorted order of the globals and everything else.
+        doc.hoist_globals();
+        assert_eq!(
+            names(&doc),
+            vec![
+                "assert", "version", "", " etc...", "controls", "key", "key", "lang", "volume",
+                "window"
+            ]
+        );
+    }
+
+    #[test]
+    fn extend() {
+        let mut doc = Document::new(Vec::new());
+        doc.extend([
+            "!version 1.0.2".parse::<Elements>().unwrap(),
+            "window width=320 height=\"2 4\""
+                .parse::<Elements>()
+                .unwrap(),
+        ]);
+
+        let mut controls = Elements::new_standard("controls".to_owned());
+        if let Elements::Standard { element, .. } = &mut controls {
+            element.upsert(KeyVal::new(Some("id".to_owned()), "0".to_owned()));
+        }
+        doc.push(controls);
+
+        let lines: Vec<usize> = doc.results.iter().map(ParseResult::line_number).collect();
+        assert_eq!(lines, vec![1, 2, 3]);
+        assert_eq!(
+            doc.format(FormatOptions::default()),
+            "!version 1.0.2\nwindow width=320 height=\"2 4\"\ncontrols id=0\n"
+        );
+
+        // Parsed documents continue after their last line.
+        let mut doc = Document::from_string("!version 1\n\nwindow\n# end", None);
+        doc.extend(["lang en".parse::<Elements>().unwrap()]);
+        assert_eq!(doc.results.last().unwrap().line_number(), 5);
+    }
+
+    #[test]
+    fn iterate_by_kind() {
+        let doc = Document::from_string(&format!("{}\n@!bad\n@dangling", CONFIG_V1), None);
+        let names = |iter: Box<dyn Iterator<Item = &Element> + '_>| -> Vec<String> {
+            iter.map(|element| element.text.clone()).collect()
+        };
+
+        assert_eq!(names(Box::new(doc.globals())), vec!["version"]);
+        assert_eq!(
+            names(Box::new(doc.standards())),
+            vec!["window", "volume", "lang", "controls", "key", "key"]
+        );
+        assert_eq!(names(Box::new(doc.attributes())), vec!["default"]);
+        assert_eq!(names(Box::new(doc.comments())), vec![" etc..."]);
+    }
+
+    #[test]
+    fn lint_recovered_prefix() {
+        let options = ParserOptions {
+            recover_prefixes: true,
+            ..Default::default()
+        };
+        let doc = Document::from_string_with_options("@!foo x=1\nwindow\n!@bar", None, options);
+
+        assert!(doc.results.iter().all(|result| !result.is_err()));
+
+        let lints: Vec<(usize, Lint, Severity)> = doc
+            .lint()
+            .into_iter()
+            .map(|d| (d.line_number, d.lint, d.severity))
+            .collect();
+        assert_eq!(
+            lints,
+            vec![
+                (1, Lint::RecoveredPrefix, Severity::Info),
+                (3, Lint::RecoveredPrefix, Severity::Info)
+            ]
+        );
+
+        let lints = doc.lint_with(&LintOptions {
+            recovered_prefixes: false,
+            ..Default::default()
+        });
+        assert!(lints.is_empty());
+    }
+}
@@ -1,11 +1,44 @@
-use std::{fmt, str::FromStr};
+use std::{collections::HashMap, fmt, str::FromStr};
 
-use crate::keyval::KeyVal;
+use crate::{enums::ErrorCodes, keyval::KeyVal, ParseError};
+
+/// [RangeError] is returned by [Element::get_key_value_range] when a key is
+/// present but its value is not valid.
+#[derive(Debug, PartialEq)]
+pub enum RangeError {
+    /// The value could not be coerced into the requested type.
+    Unparseable,
+
+    /// The value was coerced but is outside of the inclusive range.
+    OutOfRange,
+}
 
 /// The data structure [Element] used by all variants internally.
+///
+/// [Element::args] are kept in declaration order. A key which is assigned
+/// again keeps the position of its first declaration and takes the new value,
+/// so `x b=1 a=2 b=3` has the args `b=3, a=2`. See [Element::upsert] and
+/// [Element::key_order].
+///
+/// With the `serde` feature, an [Element] is serialized as its [Element::text]
+/// and an array of [Element::args], each as `{key, val}`. [Element::doc] is
+/// only written when it is not empty. In JSON `x a=1 b` reads as:
+///
+/// ```json
+/// {"text":"x","args":[{"key":"a","val":"1"},{"key":null,"val":"b"}]}
+/// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Element {
     pub text: String,
     pub args: Vec<KeyVal>,
+
+    /// The text of the comments directly before this element when
+    /// [crate::options::ParserOptions::doc_comments] is true. Otherwise empty.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub doc: Vec<String>,
 }
 
 impl Element {
@@ -15,6 +48,7 @@ impl Element {
         Element {
             text,
             args: Vec::new(),
+            doc: Vec::new(),
         }
     }
 
@@ -23,6 +57,10 @@ impl Element {
     /// or if the keyval [kv] is nameless, then simply inserts [kv] into the
     /// back of [Element::args].
     ///
+    /// Therefore [Element::args] are always in the order they were first
+    /// declared. Nameless keyvals keep their position relative to named
+    /// keyvals, and a repeated key keeps the position of its first declaration.
+    ///
     /// See [KeyVal::is_nameless].
     pub fn upsert(&mut self, kv: KeyVal) {
         // We cannot update nameless keyvals, so we insert as-is.
@@ -35,7 +73,7 @@ impl Element {
             None => false,
             Some(k) => k == kv.key.as_ref().unwrap(),
         }) {
-            self.args[prev].val = kv.val;
+            self.args[prev] = kv;
             return;
         }
 
@@ -44,31 +82,161 @@ impl Element {
         self.args.push(kv);
     }
 
+    /// Inserts [kv] into the back of [Element::args] even if a [KeyVal] with
+    /// the same [KeyVal::key] already exists, so that the key holds a list of
+    /// values. See [Element::get_key_value_vec].
+    pub fn append(&mut self, kv: KeyVal) {
+        self.args.push(kv);
+    }
+
+    /// Inserts [kv] into the back of [Element::args] even if a [KeyVal] with
+    /// the same [KeyVal::key] already exists. Unlike [Element::upsert], every
+    /// value is kept, so `tags key=a key=b` can hold both `a` and `b`. The
+    /// same as [Element::append]. See [Element::get_all].
+    pub fn insert_multi(&mut self, kv: KeyVal) {
+        self.append(kv);
+    }
+
+    /// Returns each named key in [Element::args] once, in the order it was
+    /// first declared. Nameless keyvals are skipped.
+    pub fn key_order(&self) -> Vec<&str> {
+        let mut keys: Vec<&str> = Vec::new();
+
+        for key in self.args.iter().filter_map(|kv| kv.key.as_deref()) {
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+
+        keys
+    }
+
     /// Returns true if there is a [KeyVal] in [Element::args] which has
     /// an identical [KeyVal::key] field as the input [key].
     /// Nameless keyvals will never match and always return false.
     pub fn has_key(&self, key: &str) -> bool {
-        if let Some(_) = self.args.iter().position(|arg| match &arg.key {
-            None => false,
-            Some(k) => k == key,
-        }) {
-            return true;
-        }
-
-        false
+        self.args.iter().any(|arg| arg.key.as_deref() == Some(key))
     }
 
     /// Tests every key [String] in [keys] with [Element::has_key].
     /// If and only if they are all found, then this returns true.
     /// If any key fails, then this returns false.
     pub fn has_keys(&self, keys: &Vec<&str>) -> bool {
-        for iter in keys.into_iter() {
+        for iter in keys.iter() {
             if !self.has_key(iter) {
                 return false;
             }
         }
 
-        return true;
+        true
+    }
+
+    /// Returns every [KeyVal] in [Element::args] whose [KeyVal::key] field
+    /// is [key] in the order they appear. [Element::upsert] never stores the
+    /// same key twice, but [Element::append] does.
+    /// Nameless keyvals will never match.
+    pub fn args_by_key(&self, key: &str) -> Vec<&KeyVal> {
+        self.args
+            .iter()
+            .filter(|kv| kv.key.as_deref() == Some(key))
+            .collect()
+    }
+
+    /// Returns the [KeyVal::val] of every [KeyVal] in [Element::args] whose
+    /// [KeyVal::key] is [key] in the order they appear. A key only holds more
+    /// than one value when inserted with [Element::insert_multi], such as with
+    /// [crate::options::ParserOptions::append_glyph]. See [Element::args_by_key].
+    pub fn get_all(&self, key: &str) -> Vec<&String> {
+        self.args_by_key(key)
+            .into_iter()
+            .map(|kv| &kv.val)
+            .collect()
+    }
+
+    /// Returns true if [self] and [other] mean the same thing in YES terms
+    /// even if they were written differently:
+    ///
+    /// - [Element::text] must be equal.
+    /// - Nameless args are positional, so their values must be equal and in
+    ///   the same order. `x p q` is not `x q p`.
+    /// - Named args are not positional, so the same keys must be present in
+    ///   any order. `x a=1 b=2` is `x b=2 a=1`. A key with several values
+    ///   from [Element::append] must have the same values in the same order.
+    ///
+    /// [Element::doc] and the positions of nameless args relative to named
+    /// args are ignored.
+    pub fn semantic_eq(&self, other: &Element) -> bool {
+        fn nameless(element: &Element) -> Vec<&str> {
+            element
+                .args
+                .iter()
+                .filter(|kv| kv.is_nameless())
+                .map(|kv| kv.val.as_str())
+                .collect()
+        }
+
+        fn values<'a>(element: &'a Element, key: &str) -> Vec<&'a str> {
+            element
+                .args_by_key(key)
+                .iter()
+                .map(|kv| kv.val.as_str())
+                .collect()
+        }
+
+        let keys = self.key_order();
+
+        self.text == other.text
+            && nameless(self) == nameless(other)
+            && keys.len() == other.key_order().len()
+            && keys
+                .iter()
+                .all(|key| values(self, key) == values(other, key))
+    }
+
+    /// Returns the [KeyVal::val] of the first nameless [KeyVal] in
+    /// [Element::args]. Named keyvals are skipped. See [KeyVal::is_nameless].
+    pub fn first_nameless(&self) -> Option<&str> {
+        self.get_nameless(0).map(|kv| kv.val.as_str())
+    }
+
+    /// Returns the [KeyVal::val] of the last nameless [KeyVal] in
+    /// [Element::args]. Named keyvals are skipped. See [KeyVal::is_nameless].
+    pub fn last_nameless(&self) -> Option<&str> {
+        self.args
+            .iter()
+            .rfind(|kv| kv.is_nameless())
+            .map(|kv| kv.val.as_str())
+    }
+
+    /// Returns the [index]th nameless [KeyVal] in [Element::args], counting
+    /// from zero. Named keyvals are skipped, so in `key A code=13 B` index `1`
+    /// is `B`. See [Element::nameless_count].
+    pub fn get_nameless(&self, index: usize) -> Option<&KeyVal> {
+        self.args.iter().filter(|kv| kv.is_nameless()).nth(index)
+    }
+
+    /// Returns the number of nameless [KeyVal]s in [Element::args].
+    pub fn nameless_count(&self) -> usize {
+        self.args.iter().filter(|kv| kv.is_nameless()).count()
+    }
+
+    /// Returns a map of every named [KeyVal] in [Element::args] from its
+    /// [KeyVal::key] to a copy of its [KeyVal::val]. Nameless keyvals are
+    /// skipped. If the same key appears more than once, the last one wins.
+    pub fn to_map(&self) -> HashMap<String, String> {
+        self.args
+            .iter()
+            .filter_map(|kv| kv.key.as_ref().map(|k| (k.clone(), kv.val.clone())))
+            .collect()
+    }
+
+    /// A variation of [Element::to_map] which consumes [self] and moves the
+    /// keys and values into the map instead of copying them.
+    pub fn into_map(self) -> HashMap<String, String> {
+        self.args
+            .into_iter()
+            .filter_map(|kv| kv.key.map(|k| (k, kv.val)))
+            .collect()
     }
 
     /// Finds the matching [KeyVal] whose [KeyVal::key] field is [key] and
@@ -84,15 +252,27 @@ impl Element {
             None => false,
             Some(k) => k == key,
         }) {
-            return match self.args[idx].val.parse::<T>() {
-                Ok(t) => Some(t),
-                Err(_) => None,
-            };
+            return self.args[idx].val.parse::<T>().ok();
         }
 
         None
     }
 
+    /// Like [Element::get_key_value] but tells a missing key apart from a
+    /// malformed value. Returns `Ok(None)` if no [KeyVal] has [key],
+    /// `Ok(Some(value))` if its [KeyVal::val] was coerced into [T], and the
+    /// [FromStr::Err] of [T] if it could not be.
+    pub fn try_get_key_value<T>(&self, key: &str) -> Result<Option<T>, T::Err>
+    where
+        T: FromStr,
+    {
+        self.args
+            .iter()
+            .find(|kv| kv.key.as_deref() == Some(key))
+            .map(|kv| kv.val.parse::<T>())
+            .transpose()
+    }
+
     /// A variation of [Element::get_key_value] which accepts an explicit [or]
     /// input value of type [T]. If the former method would return [None], then
     /// this method returns [or].
@@ -112,11 +292,456 @@ impl Element {
 
         or
     }
-}
 
+    /// Returns the [KeyVal::val] of the [KeyVal] whose [KeyVal::key] field is
+    /// [key] if it is exactly one [char], e.g. a key binding or separator.
+    /// A multibyte character such as `é` is one [char]. Otherwise [None].
+    pub fn get_key_value_char(&self, key: &str) -> Option<char> {
+        let kv = self.args.iter().find(|kv| kv.key.as_deref() == Some(key))?;
+
+        let mut chars = kv.val.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(c),
+            _ => None,
+        }
+    }
+
+    /// A tolerant variation of [Element::get_key_value] for numbers written
+    /// with digit separators, e.g. `1_000` or `1 000`.
+    ///
+    /// Every underscore `_` and space ` ` in the value is removed before it
+    /// is coerced into [T]. No other characters are removed, so a typo such
+    /// as `1x000` still returns [None]. Note that a value containing a space
+    /// must be quoted or comma delimited to be read as one value.
+    pub fn get_key_value_num<T>(&self, key: &str) -> Option<T>
+    where
+        T: FromStr,
+    {
+        let kv = self.args.iter().find(|kv| kv.key.as_deref() == Some(key))?;
+
+        kv.val
+            .chars()
+            .filter(|c| *c != '_' && *c != ' ')
+            .collect::<String>()
+            .parse::<T>()
+            .ok()
+    }
+
+    /// Splits the [KeyVal::val] of the first [KeyVal] whose [KeyVal::key] is
+    /// [key] on [sep] with [KeyVal::split_value] and coerces every trimmed
+    /// piece into type [T], so `ports="80, 443,8080"` yields `[80, 443, 8080]`.
+    ///
+    /// An empty value is an empty list. Empty pieces are kept, so a trailing
+    /// separator such as `80,443,` leaves an empty last piece which must also
+    /// coerce into [T]. If no such key is found or if any piece could not be
+    /// coerced into [T], then [None] is returned.
+    pub fn get_key_value_list<T>(&self, key: &str, sep: char) -> Option<Vec<T>>
+    where
+        T: FromStr,
+    {
+        self.args
+            .iter()
+            .find(|kv| kv.key.as_deref() == Some(key))?
+            .split_value(sep)
+            .into_iter()
+            .map(|piece| piece.parse::<T>().ok())
+            .collect()
+    }
+
+    /// Returns the [KeyVal::val] of every [KeyVal] whose [KeyVal::key] field is
+    /// [key], in order, coerced into type [T]. See [Element::append].
+    ///
+    /// If no such key is found, then the list is empty. If any value could not
+    /// be coerced into [T], then [None] is returned.
+    pub fn get_key_value_vec<T>(&self, key: &str) -> Option<Vec<T>>
+    where
+        T: FromStr,
+    {
+        self.args
+            .iter()
+            .filter(|kv| kv.key.as_deref() == Some(key))
+            .map(|kv| kv.val.parse::<T>().ok())
+            .collect()
+    }
+
+    /// Runs the custom check [f] on this element. If [f] returns a message,
+    /// then it is wrapped in a [ParseError] with [ErrorCodes::Runtime] and a
+    /// message prefixed with the element name, e.g. `window: missing width`.
+    ///
+    /// An [Element] does not know which line it was parsed from, so
+    /// [ParseError::line_number] is 0. See
+    /// [crate::document::Document::validate_with] to check every element
+    /// with its line number.
+    pub fn validate_with<F>(&self, f: F) -> Result<(), ParseError>
+    where
+        F: Fn(&Element) -> Result<(), String>,
+    {
+        f(self).map_err(|message| ParseError {
+            line_number: 0,
+            message: format!("{}: {}", self.text, message),
+            code: ErrorCodes::Runtime,
+            source: String::new(),
+            first_line_number: 0,
+            column: 0,
+        })
+    }
+
+    /// Deserializes the args of this element into [T]. Named args map to the
+    /// fields of [T] by key and nameless args map to the field renamed to
+    /// [crate::de::NAMELESS_KEY]. Requires the `serde` feature.
+    /// See [crate::de].
+    #[cfg(feature = "serde")]
+    pub fn try_into_typed<T>(&self) -> Result<T, crate::de::Error>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        T::deserialize(crate::de::from_element(self))
+    }
+
+    /// A variation of [Element::get_key_value] which also validates that the
+    /// value is within the inclusive range [min] to [max].
+    ///
+    /// Returns [Ok] with [None] if no such key is found, or [Ok] with the
+    /// value if it is in range. Otherwise returns [RangeError].
+    pub fn get_key_value_range<T>(&self, key: &str, min: T, max: T) -> Result<Option<T>, RangeError>
+    where
+        T: FromStr + PartialOrd,
+    {
+        let kv = match self.args.iter().find(|kv| kv.key.as_deref() == Some(key)) {
+            None => return Ok(None),
+            Some(kv) => kv,
+        };
+
+        let val = kv.val.parse::<T>().map_err(|_| RangeError::Unparseable)?;
+
+        if val < min || val > max {
+            return Err(RangeError::OutOfRange);
+        }
+
+        Ok(Some(val))
+    }
+}
 
 impl fmt::Display for Element {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} args={{{}}}", self.text, self.args.iter().map(|a| a.to_string()).collect::<Vec<String>>().join(", "))
+        write!(
+            f,
+            "{} args={{{}}}",
+            self.text,
+            self.args
+                .iter()
+                .map(|a| a.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        element::{Element, RangeError},
+        element_parser::standard,
+        enums::{Elements, ErrorCodes},
+        keyval::KeyVal,
+        literal::Literal,
+        options::ParserOptions,
+        YesDocParser,
+    };
+
+    #[test]
+    fn args_by_key() {
+        let mut element = Element::new("post".to_owned());
+        element
+            .args
+            .push(KeyVal::new(Some("tag".to_owned()), "rust".to_owned()));
+        element.args.push(KeyVal::new(None, "tag".to_owned()));
+        element
+            .args
+            .push(KeyVal::new(Some("tag".to_owned()), "yes".to_owned()));
+
+        let tags = element.args_by_key("tag");
+        assert_eq!(tags.len(), 2);
+        assert_eq!(tags[0].val, "rust");
+        assert_eq!(tags[1].val, "yes");
+
+        assert!(element.args_by_key("missing").is_empty());
+    }
+
+    #[test]
+    fn into_map() {
+        let mut element = Element::new("window".to_owned());
+        element.upsert(KeyVal::new(Some("width".to_owned()), "320".to_owned()));
+        element.upsert(KeyVal::new(Some("height".to_owned()), "240".to_owned()));
+        element.upsert(KeyVal::new(None, "fullscreen".to_owned()));
+        element
+            .args
+            .push(KeyVal::new(Some("width".to_owned()), "640".to_owned()));
+
+        let copy = element.to_map();
+        let map = element.into_map();
+        assert_eq!(copy, map);
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get("width").unwrap(), "640");
+        assert_eq!(map.get("height").unwrap(), "240");
+        assert!(!map.contains_key("fullscreen"));
+    }
+
+    #[test]
+    fn get_key_value_range() {
+        let mut element = Element::new("volume".to_owned());
+        element.upsert(KeyVal::new(Some("sfx".to_owned()), "100".to_owned()));
+        element.upsert(KeyVal::new(Some("music".to_owned()), "150".to_owned()));
+        element.upsert(KeyVal::new(Some("voice".to_owned()), "loud".to_owned()));
+
+        assert_eq!(element.get_key_value_range("sfx", 0, 100), Ok(Some(100)));
+        assert_eq!(
+            element.get_key_value_range("music", 0, 100),
+            Err(RangeError::OutOfRange)
+        );
+        assert_eq!(
+            element.get_key_value_range("voice", 0, 100),
+            Err(RangeError::Unparseable)
+        );
+        assert_eq!(element.get_key_value_range("master", 0, 100), Ok(None));
+    }
+
+    #[test]
+    fn first_and_last_nameless() {
+        let parse = |line: &str| standard(line, &None, &ParserOptions::default());
+
+        let lang = parse("lang en");
+        assert_eq!(lang.first_nameless(), Some("en"));
+        assert_eq!(lang.last_nameless(), Some("en"));
+
+        let x = parse("x a=1 first second");
+        assert_eq!(x.first_nameless(), Some("first"));
+        assert_eq!(x.last_nameless(), Some("second"));
+
+        let named = parse("x a=1 b=2");
+        assert_eq!(named.first_nameless(), None);
+        assert_eq!(named.last_nameless(), None);
+    }
+
+    #[test]
+    fn validate_with() {
+        let parse = |line: &str| standard(line, &None, &ParserOptions::default());
+
+        // Exactly one of `width` or `fullscreen` is required.
+        let rule = |element: &Element| match (element.has_key("width"), element.first_nameless()) {
+            (true, None) | (false, Some("fullscreen")) => Ok(()),
+            _ => Err("expected one of width or fullscreen".to_owned()),
+        };
+
+        assert!(parse("window width=320").validate_with(rule).is_ok());
+        assert!(parse("window fullscreen").validate_with(rule).is_ok());
+
+        let err = parse("window height=240").validate_with(rule).unwrap_err();
+        assert_eq!(err.message, "window: expected one of width or fullscreen");
+        assert!(err.code == ErrorCodes::Runtime);
+
+        assert!(parse("window width=320 fullscreen")
+            .validate_with(rule)
+            .is_err());
+    }
+
+    #[test]
+    fn key_order() {
+        let element = standard("x b=1 a=2 b=3", &None, &ParserOptions::default());
+
+        assert_eq!(element.key_order(), vec!["b", "a"]);
+        assert_eq!(element.get_key_value::<u8>("b"), Some(3));
+
+        // Appended keys are listed once.
+        let mut element = Element::new("x".to_owned());
+        element.append(KeyVal::new(Some("tag".to_owned()), "a".to_owned()));
+        element.append(KeyVal::new(Some("id".to_owned()), "1".to_owned()));
+        element.append(KeyVal::new(Some("tag".to_owned()), "b".to_owned()));
+        assert_eq!(element.key_order(), vec!["tag", "id"]);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn get_key_value_num() {
+        let element = standard(
+            "stats a=1_000, b=1 000, c=1.5, d=1x000, e=1_000.25",
+            &None,
+            &ParserOptions::default(),
+        );
+
+        assert_eq!(element.get_key_value_num::<u32>("a"), Some(1000));
+        assert_eq!(element.get_key_value_num::<u32>("b"), Some(1000));
+        assert_eq!(element.get_key_value_num::<f32>("c"), Some(1.5));
+        assert_eq!(element.get_key_value_num::<u32>("d"), None);
+        assert_eq!(element.get_key_value_num::<f64>("e"), Some(1000.25));
+        assert_eq!(element.get_key_value_num::<u32>("missing"), None);
+
+        // Separators are only stripped when asked.
+        assert_eq!(element.get_key_value::<u32>("a"), None);
+    }
+
+    #[test]
+    fn get_key_value_char() {
+        let results = YesDocParser::from_string("keys a=; b=\",,\" c=é d=ab e=", None);
+        let element = match results[0].data() {
+            Some(Elements::Standard { element, .. }) => element,
+            _ => panic!("Standard element expected!"),
+        };
+
+        assert_eq!(element.get_key_value_char("a"), Some(';'));
+        assert_eq!(element.get_key_value_char("b"), None);
+        assert_eq!(element.get_key_value_char("c"), Some('é'));
+        assert_eq!(element.get_key_value_char("d"), None);
+        assert_eq!(element.get_key_value_char("e"), None);
+        assert_eq!(element.get_key_value_char("missing"), None);
+    }
+
+    #[test]
+    fn semantic_eq() {
+        let element = |line: &str| standard(line, &None, &ParserOptions::default());
+        let eq = |a: &str, b: &str| element(a).semantic_eq(&element(b));
+
+        assert!(eq("x a=1 b=2", "x b=2 a=1"));
+        assert!(!eq("x p q", "x q p"));
+
+        // Nameless args may move around named args.
+        assert!(eq("x p a=1 q", "x a=1 p q"));
+
+        assert!(!eq("x a=1 b=2", "x a=1 b=3"));
+        assert!(!eq("x a=1 b=2", "x a=1"));
+        assert!(!eq("x a=1", "x a=1 b=2"));
+        assert!(!eq("x a=1", "y a=1"));
+        assert!(!eq("x p", "x p p"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_json() {
+        let mut element = Element::new("window".to_string());
+        element
+            .args
+            .push(KeyVal::new(None, "fullscreen".to_string()));
+        element.args.push(KeyVal::new(
+            Some("title".to_string()),
+            "My Game".to_string(),
+        ));
+
+        let json = serde_json::to_string(&element).unwrap();
+        assert_eq!(
+            json,
+            r#"{"text":"window","args":[{"key":null,"val":"fullscreen"},{"key":"title","val":"My Game"}]}"#
+        );
+
+        let read: Element = serde_json::from_str(&json).unwrap();
+        assert_eq!(read.text, element.text);
+        assert!(read.doc.is_empty());
+        assert_eq!(read.args.len(), 2);
+        assert_eq!(read.args[0].key, None);
+        assert_eq!(read.args[0].val, "fullscreen");
+        assert_eq!(read.args[1].key.as_deref(), Some("title"));
+        assert_eq!(read.args[1].val, "My Game");
+
+        // The quoting flags are recomputed so the text is written the same.
+        assert_eq!(read.to_string(), element.to_string());
+        assert_eq!(
+            read.to_string(),
+            "window args={fullscreen, title=\"My Game\"}"
+        );
+    }
+
+    #[test]
+    fn get_nameless() {
+        let parse = |line: &str| standard(line, &None, &ParserOptions::default());
+
+        let element = parse("key A code=13 B mod=shift C");
+
+        assert_eq!(element.nameless_count(), 3);
+        assert_eq!(element.get_nameless(0).unwrap().val, "A");
+        assert_eq!(element.get_nameless(1).unwrap().val, "B");
+        assert_eq!(element.get_nameless(2).unwrap().val, "C");
+        assert!(element.get_nameless(3).is_none());
+        assert!(element.get_nameless(1).unwrap().is_nameless());
+
+        let named = parse("key code=13");
+        assert_eq!(named.nameless_count(), 0);
+        assert!(named.get_nameless(0).is_none());
+    }
+
+    #[test]
+    fn get_all() {
+        let mut element = Element::new("tags".to_owned());
+        element.upsert(KeyVal::new(Some("key".to_owned()), "a".to_owned()));
+        element.upsert(KeyVal::new(Some("key".to_owned()), "b".to_owned()));
+
+        // Upsert keeps only the last value.
+        assert_eq!(element.get_all("key"), vec!["b"]);
+
+        element.args.clear();
+        element.insert_multi(KeyVal::new(Some("key".to_owned()), "a".to_owned()));
+        element.insert_multi(KeyVal::new(None, "c".to_owned()));
+        element.insert_multi(KeyVal::new(Some("key".to_owned()), "b".to_owned()));
+
+        assert_eq!(element.get_all("key"), vec!["a", "b"]);
+        assert!(element.get_all("c").is_empty());
+
+        // The parser appends when opted into with an append glyph.
+        let options = ParserOptions {
+            append_glyph: Some('+'),
+            ..Default::default()
+        };
+        let results = YesDocParser::from_string_with_options("tags key+=a key+=b", None, options);
+        match results[0].data() {
+            Some(Elements::Standard { element, .. }) => {
+                assert_eq!(element.get_all("key"), vec!["a", "b"])
+            }
+            _ => panic!("Standard element expected!"),
+        }
+    }
+
+    #[test]
+    fn get_key_value_list() {
+        let literals = Some(vec![Literal::build_quotes()]);
+        let element = standard(
+            "server ports=\"80,443,8080\" spaced=\"1 , 2\" trailing=\"1,2,\" names=\"a,b,\" empty=\"\"",
+            &literals,
+            &ParserOptions::default(),
+        );
+
+        assert_eq!(
+            element.get_key_value_list::<u16>("ports", ','),
+            Some(vec![80, 443, 8080])
+        );
+        assert_eq!(
+            element.get_key_value_list::<u8>("spaced", ','),
+            Some(vec![1, 2])
+        );
+
+        // The empty piece after a trailing separator must coerce too.
+        assert_eq!(element.get_key_value_list::<u8>("trailing", ','), None);
+        assert_eq!(
+            element.get_key_value_list::<String>("names", ','),
+            Some(vec!["a".to_owned(), "b".to_owned(), String::new()])
+        );
+
+        assert_eq!(element.get_key_value_list::<u8>("empty", ','), Some(vec![]));
+        assert_eq!(element.get_key_value_list::<u8>("missing", ','), None);
+    }
+
+    #[test]
+    fn try_get_key_value() {
+        let element = standard(
+            "window width=320 height=tall",
+            &None,
+            &ParserOptions::default(),
+        );
+
+        assert_eq!(element.try_get_key_value::<u32>("width"), Ok(Some(320)));
+        assert_eq!(element.try_get_key_value::<u32>("depth"), Ok(None));
+        assert_eq!(
+            element.try_get_key_value::<u32>("height"),
+            Err("tall".parse::<u32>().unwrap_err())
+        );
+
+        // Present but malformed values are not hidden as missing.
+        assert_eq!(element.get_key_value::<u32>("height"), None);
+    }
+}
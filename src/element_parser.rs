@@ -1,10 +1,12 @@
-use std::{cmp::min, collections::HashMap, usize};
+use std::collections::BTreeMap;
 
 use crate::{
-    enums::{Delimiters, Elements, ErrorCodes, Glyphs},
+    element::Element,
+    enums::{ControlChars, Delimiters, Elements, ErrorCodes, Glyphs},
     keyval::KeyVal,
     literal::Literal,
-    utils::StringUtils,
+    options::ParserOptions,
+    utils::{is_whitespace_char, str_unescape, str_unquote},
 };
 
 /// [ElementTypes] is a structure used to assist [ElementParser::read].
@@ -12,22 +14,50 @@ use crate::{
 enum ElementTypes {
     Standard,
     Attribute,
+    StickyAttribute,
     Global,
 }
 
 /// [TokenWalkInfo] is a structure used to assist [ElementParser::evaluateKeyVals].
-struct TokenWalkInfo {
-    /// This is the [String]] to be evaluated into a valid [KeyVal] pair.
-    pub data: String,
+struct TokenWalkInfo<'a> {
+    /// This is the slice of the line to be evaluated into a valid [KeyVal] pair.
+    pub data: &'a str,
 
     /// If non-zero, this is the [TokenWalkInfo::data] index of the [Glyphs::Equal] symbol.
     pub pivot: Option<usize>,
+
+    /// True if the value after [Self::pivot] is kept verbatim.
+    /// See [ParserOptions::raw_keys].
+    pub raw: bool,
+
+    /// The line index [Self::data] begins at. Used to report
+    /// [ElementParser::column].
+    pub start: usize,
 }
 
-impl TokenWalkInfo {
+impl<'a> TokenWalkInfo<'a> {
+    /// Constructs a [TokenWalkInfo] for [data] which begins at [start] in the
+    /// line. [equal] is the line index of the pivot, if any. A pivot which is
+    /// not inside of [data] is dropped so that slicing around it never panics.
+    fn new(data: &'a str, equal: Option<usize>, start: usize) -> TokenWalkInfo<'a> {
+        TokenWalkInfo {
+            data,
+            pivot: TokenWalkInfo::calc_pivot(equal, start).filter(|&pivot| pivot < data.len()),
+            raw: false,
+            start,
+        }
+    }
+
+    /// Returns the line index of [text], which must be a slice of
+    /// [Self::data], after skipping its leading whitespace.
+    fn column_of(&self, text: &str) -> usize {
+        let offset = text.as_ptr() as usize - self.data.as_ptr() as usize;
+        self.start + offset + (text.len() - text.trim_start_matches(is_whitespace_char).len())
+    }
+
     /// Short-hand to test if [Self::pivot] is [Some].
     pub fn has_pivot(&self) -> bool {
-        if let Some(_) = self.pivot {
+        if self.pivot.is_some() {
             return true;
         }
 
@@ -41,15 +71,7 @@ impl TokenWalkInfo {
     /// then the result value will be [Some] whose value is `x - b`. This
     /// value is used as the pivot-point while parsing.
     fn calc_pivot(a: Option<usize>, b: usize) -> Option<usize> {
-        if let Some(x) = a {
-            if x < b {
-                return None;
-            } else {
-                return Some(x - b);
-            }
-        }
-
-        return None;
+        a?.checked_sub(b)
     }
 }
 
@@ -61,20 +83,53 @@ pub struct ElementParser {
     pub element: Option<Elements>,
     pub error: Option<ErrorCodes>,
     pub line_number: usize,
+
+    /// True if the element was an [Elements::Attribute] declared with the
+    /// sticky prefix `@@`. Sticky attributes are applied to every following
+    /// [Elements::Standard] element until cleared.
+    pub sticky: bool,
+
+    /// True if tokens were dropped by [ParserOptions::max_args_per_line].
+    pub truncated: bool,
+
+    /// The error a misplaced prefix would have reported had it not been read
+    /// as part of the name by [ParserOptions::recover_prefixes].
+    pub recovered: Option<ErrorCodes>,
+
+    /// The byte index into the trimmed line of the glyph which caused
+    /// [Self::error], such as the misplaced prefix or the quote which was
+    /// never closed. Zero if there is no error or no single glyph is at fault.
+    pub column: usize,
 }
 
 impl ElementParser {
     /// Short-hand to query [Self::error] is [None].
     pub fn is_ok(&self) -> bool {
-        match self.error {
-            None => true,
-            _ => false,
-        }
+        self.error.is_none()
     }
 
-    /// Sets [Self::error] to some [ErrorCodes] value.
-    fn set_error(&mut self, error: ErrorCodes) {
+    /// Sets [Self::error] to some [ErrorCodes] value caused by the glyph at
+    /// [column] of the trimmed line. See [Self::column].
+    fn set_error(&mut self, error: ErrorCodes, column: usize) {
         self.error = Some(error);
+        self.column = column;
+    }
+
+    /// Returns true if the element being built already has [key].
+    /// See [crate::element::Element::has_key].
+    fn has_key(&self, key: &str) -> bool {
+        self.current().is_some_and(|element| element.has_key(key))
+    }
+
+    /// Returns the [Element] of [Self::element] being built, if any.
+    fn current(&self) -> Option<&Element> {
+        match self.element {
+            Some(Elements::Standard { ref element, .. }) => Some(element),
+            Some(Elements::Attribute(ref element))
+            | Some(Elements::Global(ref element))
+            | Some(Elements::Comment(ref element)) => Some(element),
+            Some(Elements::Blank) | None => None,
+        }
     }
 
     /// Sets [Self::delimiter] to some [Delimiters] value if and only if the
@@ -87,6 +142,54 @@ impl ElementParser {
         self.delimiter = delim;
     }
 
+    /// Constructs a new [ElementParser] which has not read any line yet.
+    /// The same parser can read many lines by calling [Self::reset] and then
+    /// [Self::parse] for each line.
+    pub fn new() -> ElementParser {
+        ElementParser {
+            delimiter: Delimiters::Unset,
+            element: None,
+            error: None,
+            line_number: 0,
+            sticky: false,
+            truncated: false,
+            recovered: None,
+            column: 0,
+        }
+    }
+
+    /// Clears the results of the previous line, if any, so that the next
+    /// line can be parsed with [Self::parse] as [line_number].
+    pub fn reset(&mut self, line_number: usize) {
+        self.delimiter = Delimiters::Unset;
+        self.element = None;
+        self.error = None;
+        self.line_number = line_number;
+        self.sticky = false;
+        self.truncated = false;
+        self.recovered = None;
+        self.column = 0;
+    }
+
+    /// Constructs a new [ElementParser] and parses one [line] with it.
+    /// See [Self::parse].
+    pub fn read(line_number: usize, line: &str, literals: &Option<Vec<Literal>>) -> ElementParser {
+        ElementParser::read_with_options(line_number, line, literals, &ParserOptions::default())
+    }
+
+    /// A variation of [ElementParser::read] which parses with [options].
+    pub fn read_with_options(
+        line_number: usize,
+        line: &str,
+        literals: &Option<Vec<Literal>>,
+        options: &ParserOptions,
+    ) -> ElementParser {
+        let mut p = ElementParser::new();
+        p.reset(line_number);
+        p.parse(line, literals, options);
+        p
+    }
+
     /// This is the entry-point for the entire element parsing algorithm broken
     /// down into the following steps:
     ///     - Parsing the element type and name.
@@ -98,21 +201,64 @@ impl ElementParser {
     /// order of delimiter characters are evaluated correctly to best parse the
     /// remaining [KeyVal]s. The judgement for the delimiter uses a heuristic
     /// which looks for [Glyphs::Equal] outside of string literal spans.
-    pub fn read(line_number: usize, line: &str, literals: &Option<Vec<Literal>>) -> ElementParser {
+    ///
+    /// Every position used while parsing is a byte index into the trimmed
+    /// [line]. Indices are only ever sliced at reserved [Glyphs], which are
+    /// ASCII, so every slice of [line] falls on a [char] boundary and the
+    /// line never needs to be validated as UTF-8 again.
+    ///
+    /// Call [Self::reset] before parsing another line with the same parser.
+    pub fn parse(&mut self, line: &str, literals: &Option<Vec<Literal>>, options: &ParserOptions) {
         // Step 1: Trim whitespace and start at the first valid character
-        let slice = line.trim().as_bytes();
-        let len = slice.len();
-
-        let mut p = ElementParser {
-            delimiter: Delimiters::Unset,
-            element: None,
-            error: None,
-            line_number,
+        let indented = line.starts_with(char::is_whitespace);
+        let trailing = &line[line.trim_end().len()..];
+        let line = line.trim();
+
+        let stripped;
+        let line = match options.control_chars {
+            ControlChars::Keep => line,
+            _ if !line.contains(ControlChars::is_control) => line,
+            ControlChars::Strip => {
+                stripped = line.replace(ControlChars::is_control, "");
+                stripped.trim()
+            }
+            ControlChars::Reject => {
+                let column = line.find(ControlChars::is_control).unwrap_or_default();
+                self.set_error(ErrorCodes::ControlCharacter, column);
+                return;
+            }
         };
 
+        if let Some(max) = options.max_line_length {
+            if line.len() > max {
+                self.set_error(ErrorCodes::LineTooLong, max);
+                return;
+            }
+        }
+
+        let slice = line.as_bytes();
+        let len = slice.len();
+
         if len == 0 {
-            p.set_error(ErrorCodes::EolNoData);
-            return p;
+            match options.blank_lines {
+                true => self.element = Some(Elements::Blank),
+                false => self.set_error(ErrorCodes::EolNoData, 0),
+            }
+            return;
+        }
+
+        // Comments are kept verbatim without inspecting a single token, so
+        // glyphs such as `=`, `,`, and quotes inside of them are never parsed.
+        //
+        // With [ParserOptions::comment_requires_column_zero] an indented hash
+        // is read as part of the element name instead.
+        let may_comment = !(indented && options.comment_requires_column_zero);
+        if may_comment && slice[0] == Glyphs::Hash.value() && options.reserved.contains(slice[0]) {
+            // The comment keeps the trailing whitespace of the line so that
+            // it is stored exactly as written. See [Elements::comment_text].
+            let trailing = trailing.trim_end_matches(['\r', '\n']);
+            self.element = Some(Elements::new_comment(format!("{}{}", &line[1..], trailing)));
+            return;
         }
 
         let mut element_type = ElementTypes::Standard;
@@ -122,13 +268,13 @@ impl ElementParser {
             let c = slice[pos];
 
             // Find first non-space character.
-            if c == Glyphs::Space.value() {
+            if Glyphs::is_whitespace(c) {
                 pos += 1;
                 continue;
             }
 
             // We are on our first non-reserved character.
-            if !Glyphs::is_reserved(c) {
+            if !options.reserved.contains(c) {
                 break;
             }
 
@@ -136,9 +282,21 @@ impl ElementParser {
             // then tag the element and continue searching for the name start pos
             match Glyphs::from(c) {
                 Glyphs::At => {
+                    // A second adjacent [Glyphs::At] marks a sticky attribute.
+                    if element_type == ElementTypes::Attribute && slice[pos - 1] == c {
+                        element_type = ElementTypes::StickyAttribute;
+                        pos += 1;
+                        continue;
+                    }
+
                     if element_type != ElementTypes::Standard {
-                        p.set_error(ErrorCodes::BadTokenPosAttribute);
-                        return p;
+                        if options.recover_prefixes {
+                            self.recovered = Some(ErrorCodes::BadTokenPosAttribute);
+                            break;
+                        }
+
+                        self.set_error(ErrorCodes::BadTokenPosAttribute, pos);
+                        return;
                     }
 
                     element_type = ElementTypes::Attribute;
@@ -147,66 +305,89 @@ impl ElementParser {
                 }
                 Glyphs::Bang => {
                     if element_type != ElementTypes::Standard {
-                        p.set_error(ErrorCodes::BadTokenPosBang);
-                        return p;
+                        if options.recover_prefixes {
+                            self.recovered = Some(ErrorCodes::BadTokenPosBang);
+                            break;
+                        }
+
+                        self.set_error(ErrorCodes::BadTokenPosBang, pos);
+                        return;
                     }
 
                     element_type = ElementTypes::Global;
                     pos += 1;
                     continue;
                 }
-                Glyphs::Hash => {
-                    if element_type == ElementTypes::Standard {
-                        if let Ok(str) = String::from_utf8(slice.to_owned()) {
-                            p.element = Some(Elements::new_comment(str.substring(pos + 1, len)));
-                            return p;
-                        }
-                    }
-                }
+                // A hash after another prefix begins the element name. So
+                // does a quote, which keeps the glyphs of a quoted name such
+                // as `"@foo"` from being read as prefixes.
                 _ => break,
             };
         }
 
-        // Step 3: find end of element name (first space or EOL)
-        let end = match slice.iter().position(|&b| b == Glyphs::Space.value()) {
+        // Step 3: find end of element name (first space after the name or EOL)
+        let end = match slice[pos..].iter().position(|&b| Glyphs::is_whitespace(b)) {
             None => len,
-            Some(idx) => min(len, idx),
+            Some(idx) => pos + idx,
         };
 
-        let name: String;
-        if let Ok(str) = String::from_utf8(slice.to_owned()) {
-            name = str.substring(pos, end - pos).unquote().clone();
-        } else {
-            p.set_error(match p.element {
-                Some(ref el) => match el {
-                    Elements::Attribute(_) => ErrorCodes::EolMissingAttribute,
-                    Elements::Global(_) => ErrorCodes::EolMissingGlobal,
-                    _ => ErrorCodes::EolMissingElement,
-                },
-                _ => ErrorCodes::EolMissingElement,
-            });
+        let name = str_unquote(&line[pos..end]).into_owned();
 
-            return p;
-        }
+        let is_raw = options.raw_elements.contains(&name);
 
         // Comment element case handled already above
-        p.element = Some(match element_type {
-            ElementTypes::Attribute => Elements::new_attribute(name),
+        self.sticky = element_type == ElementTypes::StickyAttribute;
+        self.element = Some(match element_type {
+            ElementTypes::Attribute | ElementTypes::StickyAttribute => {
+                Elements::new_attribute(name)
+            }
             ElementTypes::Global => Elements::new_global(name),
             _ => Elements::new_standard(name),
         });
 
         // Step 4: parse tokens, if any and return results
-        p.parse_tokens(slice, end, &literals);
-        p
+        if is_raw {
+            self.capture_raw(line, end);
+        } else {
+            self.parse_tokens(line, end, literals, options);
+        }
     }
 
-    fn parse_tokens(&mut self, slice: &[u8], mut start: usize, literals: &Option<Vec<Literal>>) {
+    /// Stores the remainder of the line after the element name verbatim as
+    /// one nameless [KeyVal]. No delimiters, literals, or quotes are processed.
+    /// See [ParserOptions::raw_elements].
+    fn capture_raw(&mut self, line: &str, mut start: usize) {
+        let slice = line.as_bytes();
+        let len = slice.len();
+
+        // Find first non-space character
+        while start < len && Glyphs::is_whitespace(slice[start]) {
+            start += 1;
+        }
+
+        if start >= len {
+            return;
+        }
+
+        self.element
+            .as_mut()
+            .unwrap()
+            .upsert_keyval(KeyVal::new(None, line[start..].to_owned()));
+    }
+
+    fn parse_tokens(
+        &mut self,
+        line: &str,
+        mut start: usize,
+        literals: &Option<Vec<Literal>>,
+        options: &ParserOptions,
+    ) {
+        let slice = line.as_bytes();
         let len = slice.len();
 
         // Find first non-space character
         while start < len {
-            if slice[start] == Glyphs::Space.value() {
+            if Glyphs::is_whitespace(slice[start]) {
                 start += 1;
                 continue;
             }
@@ -220,29 +401,38 @@ impl ElementParser {
         }
 
         // Collect and then evaluate all KeyVal args
-        let walk_info = self.collect_tokens(slice, start, &literals);
-        self.evaluate_keyvals(walk_info);
+        let walk_info = self.collect_tokens(line, start, literals, options);
+        if !self.is_ok() {
+            return;
+        }
+
+        self.evaluate_keyvals(walk_info, literals, options);
     }
 
-    fn collect_tokens(
+    fn collect_tokens<'a>(
         &mut self,
-        slice: &[u8],
+        line: &'a str,
         start: usize,
         literals: &Option<Vec<Literal>>,
-    ) -> Vec<TokenWalkInfo> {
-        let mut ud_literals = HashMap::<&Literal, Option<usize>>::new();
+        options: &ParserOptions,
+    ) -> Vec<TokenWalkInfo<'a>> {
+        let mut ud_literals = BTreeMap::<&Literal, Option<usize>>::new();
+        let escaped_quotes = Literal::build_quotes().with_escape(Glyphs::Backslash.value());
 
         // Populate our table with the provided literals, if any.
         // Initially, they're mapped value will be None.
-        match literals {
-            Some(ref list) => {
-                for literal in list {
-                    ud_literals.insert(literal, None);
-                }
+        //
+        // The table is ordered by [Literal]'s [Ord] implementation so that
+        // literals sharing the same [Literal::begin] are always tested in
+        // the same order. The literal with the smallest [Literal::end] wins.
+        if let Some(ref list) = literals {
+            for literal in list {
+                let literal = ElementParser::effective_literal(literal, &escaped_quotes, options);
+                ud_literals.insert(literal, None);
             }
-            None => (),
         }
 
+        let slice = line.as_bytes();
         let len = slice.len();
         let mut curr = start;
         let mut tokens = Vec::new();
@@ -264,12 +454,21 @@ impl ElementParser {
         let mut tokens_bf_eq: usize = 0;
         let mut tokens_af_eq: usize = 0;
         let mut token_walking = false;
+        let mut token_begin = start;
         let mut active_literal: Option<&Literal> = None;
 
+        // See [ParserOptions::raw_keys].
+        let is_raw_key = |key: &str| {
+            options
+                .raw_keys
+                .iter()
+                .any(|raw| *raw == str_unquote(key.trim_matches(is_whitespace_char)))
+        };
+
         while curr < len {
             let c = slice[curr];
             let is_comma = Glyphs::Comma.value() == c;
-            let is_space = Glyphs::Space.value() == c;
+            let is_space = Glyphs::is_whitespace(c);
             let is_equal = Glyphs::Equal.value() == c;
 
             // This variable denotes whether or not the current character
@@ -279,7 +478,7 @@ impl ElementParser {
             // string span which has not yet terminated.
             let mut is_literal = false;
 
-            if let Some(ref literal) = active_literal {
+            if let Some(literal) = active_literal {
                 if literal.end == c {
                     is_literal = true;
                 }
@@ -288,7 +487,9 @@ impl ElementParser {
                     // The leading equals char determines how the rest of the document
                     // will be parsed when no comma delimiter is set.
                     if !token_walking {
-                        if equal == None {
+                        token_begin = curr;
+
+                        if equal.is_none() {
                             tokens_bf_eq += 1;
                         } else {
                             tokens_af_eq += 1;
@@ -298,7 +499,7 @@ impl ElementParser {
                     token_walking = true;
 
                     // Clear the spaces metrics.
-                    if equal == None {
+                    if equal.is_none() {
                         spaces_bf_eq = 0;
                     } else {
                         spaces_af_eq = 0;
@@ -306,7 +507,7 @@ impl ElementParser {
                 } else if is_space {
                     if token_walking {
                         // Count spaces before and after equals character.
-                        if equal == None {
+                        if equal.is_none() {
                             spaces_bf_eq += 1;
                         } else {
                             spaces_af_eq += 1;
@@ -314,13 +515,19 @@ impl ElementParser {
                     }
                     token_walking = false;
 
-                    if space == None {
+                    if space.is_none() {
                         space = Some(curr);
                     }
                 } else if is_equal {
+                    // The rest of the line is the value of a raw key and
+                    // must not decide the delimiter.
+                    if is_raw_key(&line[token_begin..curr]) {
+                        break;
+                    }
+
                     token_walking = false;
 
-                    if equal == None {
+                    if equal.is_none() {
                         equal = Some(curr);
                     }
 
@@ -351,7 +558,7 @@ impl ElementParser {
                 // If [is_literal] is true, then [active_literal] should
                 // never be [Option::None].
                 debug_assert!(
-                    active_literal != None,
+                    active_literal.is_some(),
                     "Expected active_literal to be Some() while parsing a literal character!"
                 );
 
@@ -362,7 +569,7 @@ impl ElementParser {
 
                     // Effectively, these next two conditional branches toggle
                     // whether or not we are reading a literal span.
-                    if *value == None {
+                    if value.is_none() {
                         value.replace(curr);
                     } else {
                         value.take();
@@ -375,8 +582,8 @@ impl ElementParser {
             }
 
             // Look ahead for terminating literal
-            if let Some(ref key) = active_literal {
-                let offset: Option<usize> = slice.iter().skip(curr).position(|&b| b == key.end);
+            if let Some(key) = active_literal {
+                let offset: Option<usize> = key.find_end(slice, curr);
                 if let Some(pos) = offset {
                     curr += pos;
                     continue;
@@ -387,6 +594,15 @@ impl ElementParser {
                 }
             }
 
+            // An escaped comma or space is part of the token.
+            if options.escape_delimiter
+                && c == Glyphs::Backslash.value()
+                && matches!(slice.get(curr + 1), Some(&b) if b == Glyphs::Comma.value() || b == Glyphs::Space.value())
+            {
+                curr += 2;
+                continue;
+            }
+
             if is_comma {
                 self.set_delimiter(Delimiters::Comma);
                 break;
@@ -401,7 +617,7 @@ impl ElementParser {
             && tokens_bf_eq == 1
             && tokens_af_eq <= 1
             && spaces_bf_eq.abs_diff(spaces_af_eq) <= 1
-            && space != None;
+            && space.is_some();
 
         // EOL with no comma delimiter found.
         if self.delimiter == Delimiters::Unset {
@@ -423,42 +639,85 @@ impl ElementParser {
         curr = start;
         equal = None;
         active_literal = None;
+
+        // An unterminated span from step 1 must not carry over.
+        for value in ud_literals.values_mut() {
+            value.take();
+        }
         let mut last_token_idx = start;
 
         while curr < len {
             let c = slice[curr];
             let is_equal = Glyphs::Equal.value() == c;
-            let is_delim = self.delimiter.value() == c;
+            let is_delim = match self.delimiter {
+                Delimiters::Space => Glyphs::is_whitespace(c),
+                _ => self.delimiter.value() == c,
+            };
 
             let mut is_literal = false;
-            if let Some(ref literal) = active_literal {
+            if let Some(literal) = active_literal {
                 // Test if this is the matching end literal.
                 if literal.end == c {
                     is_literal = true
                 }
             } else {
                 // An equal glyph was found outside a string literal.
-                // Track it to help with token parsing later.
+                // Track it to help with token parsing later. Only the first
+                // equal glyph of the current token is the pivot so that any
+                // following equal glyphs belong to the value.
                 if is_equal {
-                    equal = Some(curr);
+                    if TokenWalkInfo::calc_pivot(equal, last_token_idx).is_none() {
+                        equal = Some(curr);
+
+                        // The rest of the line is the value of a raw key.
+                        if is_raw_key(&line[last_token_idx..curr]) {
+                            let mut token = TokenWalkInfo::new(
+                                line[last_token_idx..].trim_end_matches(is_whitespace_char),
+                                equal,
+                                last_token_idx,
+                            );
+                            token.raw = true;
+                            tokens.push(token);
+                            last_token_idx = len;
+                            break;
+                        }
+                    }
                     curr += 1;
                     continue;
                 }
 
+                // An escaped delimiter is part of the token.
+                if options.escape_delimiter
+                    && c == Glyphs::Backslash.value()
+                    && slice.get(curr + 1) == Some(&self.delimiter.value())
+                {
+                    curr += 2;
+                    continue;
+                }
+
                 // No active literal span indicates this delimiter is valid.
                 if is_delim {
-                    if let Ok(ref str) = String::from_utf8(slice.to_vec()) {
-                        tokens.push(TokenWalkInfo {
-                            data: str.substring(last_token_idx, curr - last_token_idx),
-                            pivot: TokenWalkInfo::calc_pivot(equal, last_token_idx),
-                        });
-                    }
+                    tokens.push(TokenWalkInfo::new(
+                        &line[last_token_idx..curr],
+                        equal,
+                        last_token_idx,
+                    ));
 
                     curr += 1;
                     last_token_idx = curr;
 
-                    while curr < len && slice[curr] == Glyphs::Space.value() {
-                        curr+=1;
+                    // One token past the limit is enough to know the line
+                    // is truncated, so the rest is never collected.
+                    if options
+                        .max_args_per_line
+                        .is_some_and(|max| tokens.len() > max)
+                    {
+                        last_token_idx = len;
+                        break;
+                    }
+
+                    while curr < len && Glyphs::is_whitespace(slice[curr]) {
+                        curr += 1;
                     }
                     continue;
                 }
@@ -476,7 +735,7 @@ impl ElementParser {
             // Ensure literals are terminated before evaluating delimiters.
             if is_literal {
                 debug_assert!(
-                    active_literal != None,
+                    active_literal.is_some(),
                     "Expected active_literal to be Some() while parsing a literal character!"
                 );
 
@@ -485,7 +744,7 @@ impl ElementParser {
                         .get_mut(key)
                         .expect("Expected key for active_literal to be valid.");
 
-                    if *value == None {
+                    if value.is_none() {
                         value.replace(curr);
                     } else {
                         value.take();
@@ -498,8 +757,8 @@ impl ElementParser {
             }
 
             // Look ahead for terminating literal
-            if let Some(ref key) = active_literal {
-                let offset: Option<usize> = slice.iter().skip(curr).position(|&b| b == key.end);
+            if let Some(key) = active_literal {
+                let offset: Option<usize> = key.find_end(slice, curr);
                 if let Some(pos) = offset {
                     curr += pos;
                     continue;
@@ -514,56 +773,1056 @@ impl ElementParser {
             curr += 1;
         }
 
+        // Edge case: a quote or custom literal span was never terminated.
+        if let Some(literal) = active_literal {
+            let is_quote = literal.begin == Glyphs::Quote.value();
+
+            // With escapes, a quote which ends with a lone backslash such as
+            // `"a\"` is always an error since its end quote is escaped.
+            // See [ParserOptions::lenient_literals].
+            if !options.lenient_literals || options.strict || (options.quote_escapes && is_quote) {
+                let column = ud_literals.get(literal).copied().flatten();
+                self.set_error(
+                    match is_quote {
+                        true => ErrorCodes::UnterminatedQuote,
+                        false => ErrorCodes::UnbalancedLiteral,
+                    },
+                    column.unwrap_or_default(),
+                );
+            }
+        }
+
         // There was a pending token remaining that was not terminated.
         if last_token_idx < len {
-            if let Ok(ref str) = String::from_utf8(slice.to_vec()) {
-                tokens.push(TokenWalkInfo {
-                    data: str.substring(last_token_idx, len - last_token_idx),
-                    pivot: TokenWalkInfo::calc_pivot(equal, last_token_idx),
-                });
-            }
+            tokens.push(TokenWalkInfo::new(
+                &line[last_token_idx..],
+                equal,
+                last_token_idx,
+            ));
         }
 
         tokens
     }
 
-    fn evaluate_keyvals(&mut self, tokens: Vec<TokenWalkInfo>) {
+    fn evaluate_keyvals(
+        &mut self,
+        tokens: Vec<TokenWalkInfo>,
+        literals: &Option<Vec<Literal>>,
+        options: &ParserOptions,
+    ) {
+        let max_args = match self.current() {
+            Some(element) if !options.max_args.is_empty() => {
+                options.max_args.get(&element.text).copied()
+            }
+            _ => None,
+        };
+
+        // Keys and values are trimmed and unquoted as borrowed slices of the
+        // token so that only one allocation is made for each of them.
         for token in tokens {
             // Edge case: token is just the equal chararacter.
             // Treat this as no key and no value.
             if let Some(&c) = token.data.as_bytes().first() {
                 if c == Glyphs::Equal.value() {
+                    if options.strict {
+                        self.set_error(ErrorCodes::BadTokenPosEqual, token.start);
+                        return;
+                    }
                     continue;
                 }
             }
 
-            let len = token.data.len();
+            // The remaining tokens are past the limit for any line.
+            if options
+                .max_args_per_line
+                .is_some_and(|max| self.current().unwrap().args.len() >= max)
+            {
+                self.truncated = true;
+                return;
+            }
+
+            // The remaining tokens are past the limit for this element.
+            if max_args.is_some_and(|max| self.current().unwrap().args.len() >= max) {
+                if options.strict {
+                    self.set_error(ErrorCodes::TooManyArgs, token.column_of(token.data));
+                }
+                return;
+            }
+
             // Named key values are seperated by equal (=) char.
             if token.has_pivot() {
-                let keyval = KeyVal::new(
-                    Some(
-                        token
-                            .data
-                            .substring(0, token.pivot.unwrap())
-                            .trim()
-                            .unquote()
-                            .clone(),
+                let pivot = token.pivot.unwrap();
+                let key = &token.data[..pivot];
+                let val = token.data[pivot + 1..].trim_matches(is_whitespace_char);
+
+                // Edge case: the key ends with the append glyph e.g. `tags+=a`.
+                let (key, append) = match options
+                    .append_glyph
+                    .and_then(|glyph| key.trim_end_matches(is_whitespace_char).strip_suffix(glyph))
+                {
+                    Some(key) => (key, true),
+                    None => (key, false),
+                };
+
+                // Edge case: the value begins with another equal glyph e.g. `a==b`.
+                if options.strict && val.as_bytes().first() == Some(&Glyphs::Equal.value()) {
+                    self.set_error(ErrorCodes::BadTokenPosEqual, token.column_of(val));
+                    return;
+                }
+
+                if options.strict_reserved_in_values {
+                    if let Some(idx) = ElementParser::find_bare_reserved(val, literals, options) {
+                        self.set_error(ErrorCodes::ReservedInValue, token.column_of(val) + idx);
+                        return;
+                    }
+                }
+
+                let keyval = match token.raw {
+                    true => KeyVal::new(
+                        Some(ElementParser::evaluate_text(key, literals, options)),
+                        val.to_owned(),
                     ),
-                    token
-                        .data
-                        .substring(token.pivot.unwrap() + 1, len - token.pivot.unwrap())
-                        .trim()
-                        .unquote()
-                        .clone(),
-                );
+                    false => KeyVal::new(
+                        Some(ElementParser::evaluate_text(key, literals, options)),
+                        ElementParser::evaluate_text(val, literals, options),
+                    )
+                    .with_literal_name(ElementParser::literal_name(val, literals)),
+                };
+
+                if append {
+                    self.element.as_mut().unwrap().append_keyval(keyval);
+                    continue;
+                }
+
+                // Edge case: the same key is assigned twice e.g. `a=1 a=2`.
+                if options.strict && self.has_key(keyval.key.as_ref().unwrap()) {
+                    self.set_error(ErrorCodes::DuplicateKey, token.column_of(token.data));
+                    return;
+                }
 
                 self.element.as_mut().unwrap().upsert_keyval(keyval);
                 continue;
             }
 
+            if options.strict_reserved_in_values {
+                let val = token.data.trim_matches(is_whitespace_char);
+                if let Some(idx) = ElementParser::find_bare_reserved(val, literals, options) {
+                    self.set_error(ErrorCodes::ReservedInValue, token.column_of(val) + idx);
+                    return;
+                }
+            }
+
             // Upsert the nameless key value
-            let keyval = KeyVal::new(None, token.data.clone().trim().unquote().to_string());
+            let keyval = KeyVal::new(
+                None,
+                ElementParser::evaluate_text(token.data, literals, options),
+            )
+            .with_literal_name(ElementParser::literal_name(token.data, literals));
             self.element.as_mut().unwrap().upsert_keyval(keyval);
         }
     }
+
+    /// Trims and unquotes one key or value. If [ParserOptions::trim_literals]
+    /// is true and [text] is one literal span, then the whitespace inside the
+    /// span is trimmed as well. If [ParserOptions::escape_delimiter] is true
+    /// and [text] is not a literal span, then escaped delimiters are unescaped.
+    fn evaluate_text(
+        text: &str,
+        literals: &Option<Vec<Literal>>,
+        options: &ParserOptions,
+    ) -> String {
+        let space = Glyphs::Space.value() as char;
+        let text = text.trim_matches(is_whitespace_char);
+
+        // Escapes inside of a span are removed before anything else.
+        let unescaped;
+        let text = match ElementParser::span_literal(text, literals) {
+            Some(literal) if literal.escape.is_some() => {
+                let len = text.len();
+                unescaped = format!(
+                    "{}{}{}",
+                    literal.begin as char,
+                    literal.unescape(&text[1..len - 1]),
+                    literal.end as char
+                );
+                unescaped.as_str()
+            }
+            _ => text,
+        };
+
+        if options.escape_delimiter && ElementParser::span_literal(text, literals).is_none() {
+            let backslash = Glyphs::Backslash.value() as char;
+            let comma = Glyphs::Comma.value() as char;
+            return text
+                .replace(&format!("{}{}", backslash, comma), &comma.to_string())
+                .replace(&format!("{}{}", backslash, space), &space.to_string());
+        }
+
+        let trimmed;
+        let text = match options.trim_literals {
+            true => match ElementParser::trim_literal(text, literals) {
+                Some(t) => {
+                    trimmed = t;
+                    trimmed.as_str()
+                }
+                None => text,
+            },
+            false => text,
+        };
+
+        // A span keeps or removes its delimiters as its literal decides.
+        match ElementParser::span_literal(text, literals) {
+            Some(literal) if literal.strip_delimiters => {
+                let inner = text.get(1..text.len() - 1).unwrap_or(text);
+
+                match options.quote_escapes && literal.begin == Glyphs::Quote.value() {
+                    true => str_unescape(inner).into_owned(),
+                    false => inner.to_owned(),
+                }
+            }
+            Some(_) => text.to_owned(),
+            None => str_unquote(text).into_owned(),
+        }
+    }
+
+    /// If [text] begins and ends with the same [Literal], then this returns
+    /// [text] with the whitespace inside the literal span trimmed.
+    fn trim_literal(text: &str, literals: &Option<Vec<Literal>>) -> Option<String> {
+        let len = text.len();
+        let literal = ElementParser::span_literal(text, literals)?;
+
+        let inner = text.get(1..len - 1)?.trim_matches(is_whitespace_char);
+
+        Some(format!(
+            "{}{}{}",
+            literal.begin as char, inner, literal.end as char
+        ))
+    }
+
+    /// Returns the index of the first glyph of [ParserOptions::reserved] other
+    /// than [Glyphs::Quote] in [text] if [text] is not one literal span. A
+    /// delimiter escaped by [ParserOptions::escape_delimiter] is not bare.
+    fn find_bare_reserved(
+        text: &str,
+        literals: &Option<Vec<Literal>>,
+        options: &ParserOptions,
+    ) -> Option<usize> {
+        if ElementParser::span_literal(text, literals).is_some() {
+            return None;
+        }
+
+        let mut bytes = text.bytes().enumerate();
+        while let Some((i, c)) = bytes.next() {
+            if options.escape_delimiter && c == Glyphs::Backslash.value() {
+                bytes.next();
+                continue;
+            }
+
+            if c != Glyphs::Quote.value() && options.reserved.contains(c) {
+                return Some(i);
+            }
+        }
+
+        None
+    }
+
+    /// Returns the [Literal::name] of the literal whose span is all of
+    /// [text], ignoring surrounding spaces, if it has one.
+    fn literal_name(text: &str, literals: &Option<Vec<Literal>>) -> Option<String> {
+        let text = text.trim_matches(is_whitespace_char);
+        ElementParser::span_literal(text, literals)?.name.clone()
+    }
+
+    /// Returns [escaped_quotes] in place of the quotes [literal] when
+    /// [ParserOptions::quote_escapes] is true, since a backslash then escapes
+    /// the next byte inside of quotes. Otherwise returns [literal].
+    fn effective_literal<'a>(
+        literal: &'a Literal,
+        escaped_quotes: &'a Literal,
+        options: &ParserOptions,
+    ) -> &'a Literal {
+        let quote = Glyphs::Quote.value();
+
+        match options.quote_escapes && literal.begin == quote && literal.end == quote {
+            true => escaped_quotes,
+            false => literal,
+        }
+    }
+
+    /// Returns the [Literal::begin] byte of the literal span which is still
+    /// open at the end of [line], if any. Spans are walked the same way as
+    /// when the line is parsed, so a quote inside of a custom literal such as
+    /// `[a"b]` or a quote escaped by [ParserOptions::quote_escapes] does not
+    /// open a span.
+    pub(crate) fn open_literal(
+        line: &str,
+        literals: &Option<Vec<Literal>>,
+        options: &ParserOptions,
+    ) -> Option<u8> {
+        let escaped_quotes = Literal::build_quotes().with_escape(Glyphs::Backslash.value());
+
+        // Literals sharing the same [Literal::begin] are tested in the same
+        // order as [ElementParser::collect_tokens].
+        let mut ordered: Vec<&Literal> = literals
+            .iter()
+            .flatten()
+            .map(|literal| ElementParser::effective_literal(literal, &escaped_quotes, options))
+            .collect();
+        ordered.sort();
+
+        let bytes = line.as_bytes();
+        let mut curr = 0;
+
+        while curr < bytes.len() {
+            match ordered.iter().find(|literal| literal.begin == bytes[curr]) {
+                Some(literal) => match literal.find_end(bytes, curr + 1) {
+                    Some(offset) => curr += offset + 2,
+                    None => return Some(literal.begin),
+                },
+                None => curr += 1,
+            }
+        }
+
+        None
+    }
+
+    /// Returns the [Literal] which [text] begins and ends with, if any.
+    fn span_literal<'a>(text: &str, literals: &'a Option<Vec<Literal>>) -> Option<&'a Literal> {
+        let bytes = text.as_bytes();
+        let len = bytes.len();
+
+        // A literal byte which is not ASCII may be part of a multibyte
+        // character, which cannot be sliced off of the span.
+        if len < 2 || !text.is_char_boundary(1) || !text.is_char_boundary(len - 1) {
+            return None;
+        }
+
+        literals
+            .iter()
+            .flatten()
+            .find(|l| l.begin == bytes[0] && l.end == bytes[len - 1])
+    }
+}
+
+impl Default for ElementParser {
+    fn default() -> Self {
+        ElementParser::new()
+    }
+}
+
+/// Reads [line] and returns its [Element], or panics if [line] is not a
+/// standard element. Shared by the tests of the crate.
+#[cfg(test)]
+pub(crate) fn standard(
+    line: &str,
+    literals: &Option<Vec<Literal>>,
+    options: &ParserOptions,
+) -> Element {
+    match ElementParser::read_with_options(1, line, literals, options).element {
+        Some(Elements::Standard { element, .. }) => element,
+        _ => panic!("Standard element expected for {}!", line),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        element_parser::{standard, ElementParser},
+        enums::{ControlChars, Elements, ErrorCodes, GlyphSet},
+        literal::Literal,
+        options::ParserOptions,
+    };
+
+    /// Returns the key and value of every arg of the standard element [line].
+    fn standard_args(
+        line: &str,
+        literals: &Option<Vec<Literal>>,
+        options: &ParserOptions,
+    ) -> Vec<(Option<String>, String)> {
+        standard(line, literals, options)
+            .args
+            .into_iter()
+            .map(|kv| (kv.key, kv.val))
+            .collect()
+    }
+
+    fn kv(key: &str, val: &str) -> (Option<String>, String) {
+        (Some(key.to_owned()), val.to_owned())
+    }
+
+    #[test]
+    fn reuse_parser() {
+        let literals = Some(vec![Literal::build_quotes()]);
+        let options = ParserOptions::default();
+        let mut p = ElementParser::new();
+
+        p.reset(1);
+        p.parse("@@sticky", &literals, &options);
+        assert!(p.is_ok());
+        assert!(p.sticky);
+
+        p.reset(2);
+        p.parse("", &literals, &options);
+        assert!(!p.is_ok());
+        assert!(!p.sticky);
+
+        p.reset(3);
+        p.parse("frame width=10", &literals, &options);
+        assert!(p.is_ok());
+        assert_eq!(p.line_number, 3);
+
+        match p.element {
+            Some(Elements::Standard { ref element, .. }) => {
+                assert_eq!(element.text, "frame");
+                assert_eq!(element.get_key_value::<i32>("width"), Some(10));
+            }
+            _ => panic!("Standard element expected!"),
+        }
+    }
+
+    #[test]
+    fn multibyte_names_keys_and_values() {
+        let literals = Some(vec![Literal::build_quotes()]);
+
+        let p = ElementParser::read(
+            1,
+            "héllo wörld=ünï \"ключ\"=\"значение с пробелом\" 日本",
+            &literals,
+        );
+        assert!(p.is_ok());
+
+        match p.element {
+            Some(Elements::Standard { ref element, .. }) => {
+                assert_eq!(element.text, "héllo");
+                assert_eq!(element.get_key_value::<String>("wörld").unwrap(), "ünï");
+                assert_eq!(
+                    element.get_key_value::<String>("ключ").unwrap(),
+                    "значение с пробелом"
+                );
+                assert_eq!(element.args[2].val, "日本");
+            }
+            _ => panic!("Standard element expected!"),
+        }
+
+        let p = ElementParser::read(2, "!ñame ä = b", &literals);
+        match p.element {
+            Some(Elements::Global(ref element)) => {
+                assert_eq!(element.text, "ñame");
+                assert_eq!(element.get_key_value::<String>("ä").unwrap(), "b");
+            }
+            _ => panic!("Global element expected!"),
+        }
+
+        let p = ElementParser::read(3, "#çomment ☃", &literals);
+        match p.element {
+            Some(Elements::Comment(ref element)) => assert_eq!(element.text, "çomment ☃"),
+            _ => panic!("Comment element expected!"),
+        }
+    }
+
+    #[test]
+    fn spaces_after_prefix() {
+        let literals = Some(vec![Literal::build_quotes()]);
+
+        let p = ElementParser::read(1, "   @    foo answer_to_life = 42", &literals);
+        assert!(p.is_ok());
+
+        match p.element {
+            Some(Elements::Attribute(ref element)) => {
+                assert_eq!(element.text, "foo");
+                assert_eq!(element.get_key_value::<i32>("answer_to_life"), Some(42));
+            }
+            _ => panic!("Attribute element expected!"),
+        }
+
+        let p = ElementParser::read(2, "@#foo", &literals);
+        match p.element {
+            Some(Elements::Attribute(ref element)) => assert_eq!(element.text, "#foo"),
+            _ => panic!("Attribute element expected!"),
+        }
+    }
+
+    #[test]
+    fn comment_short_circuit() {
+        let literals = Some(vec![Literal::build_quotes()]);
+
+        for line in [
+            "# a=b, \"c\" d",
+            "  # a=b, \"c\" d",
+            "# \"unterminated a==b",
+        ] {
+            let mut p = ElementParser::new();
+            p.reset(1);
+            p.parse(
+                line,
+                &literals,
+                &ParserOptions {
+                    strict: true,
+                    ..Default::default()
+                },
+            );
+            assert!(p.is_ok());
+
+            match p.element {
+                Some(Elements::Comment(ref element)) => {
+                    assert_eq!(element.text, line.trim()[1..]);
+                    assert!(element.args.is_empty());
+                }
+                _ => panic!("Comment element expected!"),
+            }
+        }
+    }
+
+    #[test]
+    fn escape_delimiter() {
+        let literals = Some(vec![Literal::build_quotes()]);
+        let options = ParserOptions {
+            escape_delimiter: true,
+            ..Default::default()
+        };
+
+        let args = |line: &str, options: &ParserOptions| standard_args(line, &literals, options);
+
+        // An escaped comma under comma delimiting.
+        assert_eq!(
+            args(r"x a=1\,2, b=3", &options),
+            vec![
+                (Some("a".to_owned()), "1,2".to_owned()),
+                (Some("b".to_owned()), "3".to_owned())
+            ]
+        );
+
+        // An escaped comma does not choose the comma delimiter.
+        assert_eq!(
+            args(r"x a=1\,2 b=3", &options),
+            vec![
+                (Some("a".to_owned()), "1,2".to_owned()),
+                (Some("b".to_owned()), "3".to_owned())
+            ]
+        );
+
+        // An escaped space under space delimiting.
+        assert_eq!(
+            args(r"x my\ tag b", &options),
+            vec![(None, "my tag".to_owned()), (None, "b".to_owned())]
+        );
+
+        // Literal spans are kept verbatim.
+        assert_eq!(
+            args(r#"x "my\ tag" b"#, &options),
+            vec![(None, r"my\ tag".to_owned()), (None, "b".to_owned())]
+        );
+
+        // Without the option the backslash is an ordinary character.
+        assert_eq!(
+            args(r"x a=1\,2", &ParserOptions::default()),
+            vec![
+                (Some("a".to_owned()), r"1\".to_owned()),
+                (None, "2".to_owned())
+            ]
+        );
+    }
+
+    #[test]
+    fn strict_reserved_in_values() {
+        let literals = Some(vec![Literal::build_quotes()]);
+        let strict = ParserOptions {
+            strict_reserved_in_values: true,
+            ..Default::default()
+        };
+
+        for line in ["note type=x!y", "note x!y", "note a=b=c", "note tag=#1"] {
+            let p = ElementParser::read_with_options(1, line, &literals, &strict);
+            assert!(!p.is_ok(), "{} should be rejected", line);
+            assert!(p.error == Some(ErrorCodes::ReservedInValue));
+
+            // Lenient parsing keeps the glyph in the value.
+            let p = ElementParser::read(1, line, &literals);
+            assert!(p.is_ok());
+        }
+
+        for line in ["note type=\"x!y\"", "note \"x!y\"", "note type=xy"] {
+            let p = ElementParser::read_with_options(1, line, &literals, &strict);
+            assert!(p.is_ok(), "{} should be accepted", line);
+        }
+
+        // Only the glyphs of the reserved set are rejected.
+        let custom = ParserOptions {
+            reserved: GlyphSet::RESERVED.without(b'#'),
+            ..strict
+        };
+        let p = ElementParser::read_with_options(1, "note tag=#x", &literals, &custom);
+        assert!(p.is_ok());
+        let p = ElementParser::read_with_options(1, "note tag=x!y", &literals, &custom);
+        assert!(p.error == Some(ErrorCodes::ReservedInValue));
+    }
+
+    #[test]
+    fn named_literals() {
+        let literals = Some(vec![
+            Literal::new(b'<', b'>').unwrap().with_name("date"),
+            Literal::new(b'[', b']').unwrap().with_name("list"),
+            Literal::new(b'{', b'}').unwrap(),
+        ]);
+
+        let element = standard(
+            "event when=<2024-01-01> tags=[a b] {raw} plain",
+            &literals,
+            &ParserOptions::default(),
+        );
+
+        let names: Vec<Option<&str>> = element.args.iter().map(|kv| kv.literal_name()).collect();
+        assert_eq!(names, vec![Some("date"), Some("list"), None, None]);
+        assert_eq!(element.args[0].val, "<2024-01-01>");
+    }
+
+    #[test]
+    fn control_chars() {
+        let line = "ding\u{0}x=1\u{7} bell";
+        let parse = |control_chars| {
+            let options = ParserOptions {
+                control_chars,
+                ..Default::default()
+            };
+            let mut parser = ElementParser::new();
+            parser.reset(1);
+            parser.parse(line, &None, &options);
+            parser
+        };
+
+        // By default control characters are kept as-is.
+        match parse(ControlChars::Keep).element {
+            Some(Elements::Standard { element, .. }) => {
+                assert_eq!(element.text, "ding\u{0}x=1\u{7}");
+            }
+            _ => panic!("Standard element expected!"),
+        }
+
+        match parse(ControlChars::Strip).element {
+            Some(Elements::Standard { element, .. }) => {
+                assert_eq!(element.text, "dingx=1");
+                assert_eq!(element.args[0].val, "bell");
+            }
+            _ => panic!("Standard element expected!"),
+        }
+
+        let parser = parse(ControlChars::Reject);
+        assert!(parser.element.is_none());
+        assert!(parser.error == Some(ErrorCodes::ControlCharacter));
+
+        // Tabs are whitespace, not control characters.
+        assert!(!ControlChars::is_control('\t'));
+        assert!(ControlChars::is_control('\u{7f}'));
+    }
+
+    #[test]
+    fn max_args() {
+        let mut options = ParserOptions::default();
+        options.max_args.insert("lang".to_owned(), 1);
+
+        let parse = |line: &str, options: &ParserOptions| {
+            let mut parser = ElementParser::new();
+            parser.reset(1);
+            parser.parse(line, &None, options);
+            parser
+        };
+
+        match parse("lang en fr de", &options).element {
+            Some(Elements::Standard { element, .. }) => {
+                assert_eq!(element.args.len(), 1);
+                assert_eq!(element.args[0].val, "en");
+            }
+            _ => panic!("Standard element expected!"),
+        }
+
+        // Other elements have no limit.
+        match parse("langs en fr de", &options).element {
+            Some(Elements::Standard { element, .. }) => assert_eq!(element.args.len(), 3),
+            _ => panic!("Standard element expected!"),
+        }
+
+        options.strict = true;
+        assert!(parse("lang en", &options).error.is_none());
+        assert!(parse("lang en fr", &options).error == Some(ErrorCodes::TooManyArgs));
+    }
+
+    #[test]
+    fn strip_delimiters() {
+        let literals = Some(vec![
+            Literal::build_quotes(),
+            Literal::new(b'`', b'`').unwrap(),
+            Literal::new(b'[', b']')
+                .unwrap()
+                .with_strip_delimiters(true),
+        ]);
+
+        let element = standard(
+            "run `a b` [1 2] \"c d\"",
+            &literals,
+            &ParserOptions::default(),
+        );
+
+        let vals: Vec<&str> = element.args.iter().map(|kv| kv.val.as_str()).collect();
+        assert_eq!(vals, vec!["`a b`", "1 2", "c d"]);
+    }
+
+    #[test]
+    fn pivot_boundaries() {
+        let quotes = Some(vec![Literal::build_quotes()]);
+        let args = |line: &str| standard_args(line, &quotes, &ParserOptions::default());
+
+        // An empty value after the pivot, including as the last character.
+        assert_eq!(args("x a="), vec![kv("a", "")]);
+        assert_eq!(args("x a=, b="), vec![kv("a", ""), kv("b", "")]);
+        assert_eq!(args("x a= b"), vec![kv("a", "b")]);
+        assert_eq!(args("x a=b="), vec![kv("a", "b=")]);
+        assert_eq!(args("x \"a\"="), vec![kv("a", "")]);
+
+        // A pivot as the first character has no key and is skipped.
+        assert!(args("x =").is_empty());
+        assert!(args("x ==").is_empty());
+
+        // An unterminated quote swallows the pivot and is an error.
+        let parser = ElementParser::read(1, "x \"a=", &quotes);
+        assert!(parser.error == Some(ErrorCodes::UnterminatedQuote));
+    }
+
+    #[test]
+    fn crafted_lines_do_not_panic() {
+        let literals = Some(vec![
+            Literal::build_quotes(),
+            Literal::new(b'[', b']').unwrap().with_escape(b'\\'),
+            // Both bytes of `é`, which are not characters on their own.
+            Literal::new(0xC3, 0xA9).unwrap().with_escape(b'\\'),
+        ]);
+        let options = ParserOptions {
+            escape_delimiter: true,
+            trim_literals: true,
+            ..Default::default()
+        };
+
+        let alphabet = ["x", " ", "=", ",", "\"", "[", "]", "\\", "é", "@", "!"];
+        let mut lines = vec![String::new()];
+
+        // Every line of up to four pieces from the alphabet.
+        for _ in 0..4 {
+            lines = lines
+                .iter()
+                .flat_map(|line| {
+                    alphabet
+                        .iter()
+                        .map(move |piece| format!("{}{}", line, piece))
+                })
+                .collect();
+
+            for line in &lines {
+                for strict in [false, true] {
+                    let options = ParserOptions {
+                        strict,
+                        ..options.clone()
+                    };
+                    ElementParser::read_with_options(
+                        1,
+                        &format!("x {}", line),
+                        &literals,
+                        &options,
+                    );
+                }
+            }
+        }
+
+        let element = standard("x é", &literals, &ParserOptions::default());
+        assert_eq!(element.args[0].val, "é");
+    }
+
+    #[test]
+    fn raw_keys() {
+        let options = ParserOptions {
+            raw_keys: vec!["cmd".to_owned()],
+            ..Default::default()
+        };
+        let quotes = Some(vec![Literal::build_quotes()]);
+        let args = |line: &str| standard_args(line, &quotes, &options);
+
+        assert_eq!(
+            args("run cmd=rm -rf /tmp label=cleanup"),
+            vec![kv("cmd", "rm -rf /tmp label=cleanup")]
+        );
+
+        // Tokens before the raw key are parsed as usual and quotes are kept.
+        assert_eq!(
+            args("run label=cleanup, \"cmd\" = echo \"a, b\"  "),
+            vec![kv("label", "cleanup"), kv("cmd", "echo \"a, b\"")]
+        );
+
+        // A comma in the raw value does not decide the delimiter.
+        assert_eq!(
+            args("run label=x cmd=echo a,b"),
+            vec![kv("label", "x"), kv("cmd", "echo a,b")]
+        );
+        assert_eq!(args("run cmd=echo a,b"), vec![kv("cmd", "echo a,b")]);
+
+        // Other keys and quoted equal glyphs are not raw.
+        assert_eq!(
+            args("run label=\"cmd=x\" y=1"),
+            vec![kv("label", "cmd=x"), kv("y", "1")]
+        );
+        assert_eq!(args("run cmd="), vec![kv("cmd", "")]);
+    }
+
+    #[test]
+    fn tabs() {
+        let quotes = Some(vec![Literal::build_quotes()]);
+        let parse = |line: &str| {
+            let element = standard(line, &quotes, &ParserOptions::default());
+            let args: Vec<_> = element
+                .args
+                .into_iter()
+                .map(|kv| (kv.key, kv.val))
+                .collect();
+            (element.text, args)
+        };
+
+        // Tabs separate and surround tokens just like spaces.
+        assert_eq!(
+            parse("\tframe\twidth=1\theight=2"),
+            (
+                "frame".to_owned(),
+                vec![kv("width", "1"), kv("height", "2")]
+            )
+        );
+        assert_eq!(
+            parse("frame a=1,\tb = \t2\t"),
+            ("frame".to_owned(), vec![kv("a", "1"), kv("b", "2")])
+        );
+
+        assert_eq!(
+            parse("\t\tframe width=1"),
+            ("frame".to_owned(), vec![kv("width", "1")])
+        );
+
+        // Form feeds and vertical tabs are whitespace too.
+        assert_eq!(
+            parse("frame\x0Cwidth=1\x0B height=2"),
+            (
+                "frame".to_owned(),
+                vec![kv("width", "1"), kv("height", "2")]
+            )
+        );
+
+        // Tabs inside of quotes are kept.
+        assert_eq!(
+            parse("frame a=\"\t1\""),
+            ("frame".to_owned(), vec![kv("a", "\t1")])
+        );
+    }
+
+    #[test]
+    fn comment_requires_column_zero() {
+        let mut options = ParserOptions::default();
+        let parse = |line: &str, options: &ParserOptions| {
+            ElementParser::read_with_options(1, line, &None, options)
+                .element
+                .unwrap()
+        };
+
+        assert!(matches!(parse("  # x", &options), Elements::Comment(_)));
+        assert!(matches!(parse("# x", &options), Elements::Comment(_)));
+
+        options.comment_requires_column_zero = true;
+        assert!(matches!(parse("# x", &options), Elements::Comment(_)));
+        assert!(matches!(
+            parse("\t# x", &options),
+            Elements::Standard { .. }
+        ));
+
+        match parse("  # x", &options) {
+            Elements::Standard { element, .. } => {
+                assert_eq!(element.text, "#");
+                assert_eq!(element.args[0].val, "x");
+            }
+            _ => panic!("Standard element expected!"),
+        }
+    }
+
+    #[test]
+    fn quote_escapes() {
+        let quotes = Some(vec![Literal::build_quotes()]);
+        let mut options = ParserOptions {
+            quote_escapes: true,
+            ..Default::default()
+        };
+        let parse = |line: &str, options: &ParserOptions| {
+            ElementParser::read_with_options(1, line, &quotes, options)
+        };
+        let vals = |parser: ElementParser| -> Vec<String> {
+            match parser.element {
+                Some(Elements::Standard { element, .. }) => {
+                    element.args.iter().map(|kv| kv.val.clone()).collect()
+                }
+                _ => panic!("Standard element expected!"),
+            }
+        };
+
+        assert_eq!(
+            vals(parse(r#"x v="a\"b", w=1"#, &options)),
+            vec!["a\"b", "1"]
+        );
+        assert_eq!(
+            vals(parse(r#"x "line1\nline2" "a\tb" "c\\" "\d""#, &options)),
+            vec!["line1\nline2", "a\tb", "c\\", "\\d"]
+        );
+        // Keys are decoded as well.
+        match parse(r#"x "a\"b"=1"#, &options).element {
+            Some(Elements::Standard { element, .. }) => {
+                assert_eq!(element.args[0].key.as_deref(), Some("a\"b"))
+            }
+            _ => panic!("Standard element expected!"),
+        }
+
+        // A lone trailing backslash escapes the end quote.
+        let parser = parse(r#"x v="C:\dir\""#, &options);
+        assert!(parser.error == Some(ErrorCodes::UnterminatedQuote));
+
+        // Without the option backslashes are kept as-is.
+        options.quote_escapes = false;
+        assert_eq!(vals(parse(r#"x v="C:\dir\""#, &options)), vec!["C:\\dir\\"]);
+        assert_eq!(vals(parse(r#"x "a\nb""#, &options)), vec!["a\\nb"]);
+    }
+
+    #[test]
+    fn comment_spacing() {
+        for (line, raw) in [
+            ("#   spaced   comment   ", "   spaced   comment   "),
+            ("  #   spaced   comment \t\r", "   spaced   comment \t"),
+            ("#", ""),
+        ] {
+            let element = ElementParser::read(1, line, &None).element.unwrap();
+            assert_eq!(element.comment_text(), Some(raw));
+            assert_eq!(element.comment_trimmed(), Some(raw.trim()));
+        }
+
+        let element = ElementParser::read(1, "x  ", &None).element.unwrap();
+        assert_eq!(element.comment_text(), None);
+    }
+
+    #[test]
+    fn quoted_reserved_names() {
+        let literals = Some(vec![Literal::build_quotes()]);
+
+        let standard = |line: &str| standard(line, &literals, &ParserOptions::default());
+
+        let element = standard("\"@foo\" x=1");
+        assert_eq!(element.text, "@foo");
+        assert_eq!(element.get_key_value::<u8>("x"), Some(1));
+
+        let element = standard("\"#notcomment\" y=2");
+        assert_eq!(element.text, "#notcomment");
+        assert_eq!(element.get_key_value::<u8>("y"), Some(2));
+
+        let element = standard("\"!bang\"");
+        assert_eq!(element.text, "!bang");
+
+        let element = standard("\"123\" z=3");
+        assert_eq!(element.text, "123");
+        assert_eq!(element.get_key_value::<u8>("z"), Some(3));
+
+        // A quoted name after a prefix is read literally as well.
+        match ElementParser::read(1, "!\"@foo\" x=1", &literals).element {
+            Some(Elements::Global(element)) => assert_eq!(element.text, "@foo"),
+            _ => panic!("Global element expected!"),
+        }
+    }
+
+    #[test]
+    fn recover_prefixes() {
+        let mut options = ParserOptions::default();
+        let read = |line: &str, options: &ParserOptions| {
+            ElementParser::read_with_options(1, line, &None, options)
+        };
+
+        let p = read("@!foo x=1", &options);
+        assert_eq!(p.error, Some(ErrorCodes::BadTokenPosBang));
+        assert!(p.element.is_none());
+
+        options.recover_prefixes = true;
+
+        // The first prefix is kept and the second begins the name.
+        let p = read("@!foo x=1", &options);
+        assert!(p.is_ok());
+        assert_eq!(p.recovered, Some(ErrorCodes::BadTokenPosBang));
+        match p.element {
+            Some(Elements::Attribute(ref element)) => {
+                assert_eq!(element.text, "!foo");
+                assert_eq!(element.get_key_value::<u8>("x"), Some(1));
+            }
+            _ => panic!("Attribute element expected!"),
+        }
+
+        let p = read("!@foo y=2", &options);
+        assert_eq!(p.recovered, Some(ErrorCodes::BadTokenPosAttribute));
+        match p.element {
+            Some(Elements::Global(ref element)) => {
+                assert_eq!(element.text, "@foo");
+                assert_eq!(element.get_key_value::<u8>("y"), Some(2));
+            }
+            _ => panic!("Global element expected!"),
+        }
+
+        // Well-formed prefixes are not recovered.
+        let p = read("@@sticky", &options);
+        assert!(p.sticky);
+        assert_eq!(p.recovered, None);
+    }
+
+    #[test]
+    fn error_columns() {
+        let strict = ParserOptions {
+            strict: true,
+            ..Default::default()
+        };
+        let quotes = Some(vec![Literal::build_quotes()]);
+        let column = |line: &str, options: &ParserOptions| {
+            let p = ElementParser::read_with_options(1, line, &quotes, options);
+            (p.error, p.column)
+        };
+
+        // The column is a byte index into the trimmed line.
+        assert_eq!(
+            column("  @!foo x=1", &strict),
+            (Some(ErrorCodes::BadTokenPosBang), 1)
+        );
+        assert_eq!(
+            column("!!foo", &strict),
+            (Some(ErrorCodes::BadTokenPosBang), 1)
+        );
+        assert_eq!(
+            column("x a=\"abc", &strict),
+            (Some(ErrorCodes::UnterminatedQuote), 4)
+        );
+        assert_eq!(
+            column("x b=1, a = \"abc", &strict),
+            (Some(ErrorCodes::UnterminatedQuote), 11)
+        );
+        assert_eq!(
+            column("x a==b", &strict),
+            (Some(ErrorCodes::BadTokenPosEqual), 4)
+        );
+        assert_eq!(
+            column("x a=1 b=2 a=3", &strict),
+            (Some(ErrorCodes::DuplicateKey), 10)
+        );
+
+        let reserved = ParserOptions {
+            strict_reserved_in_values: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            column("x a=b@c", &reserved),
+            (Some(ErrorCodes::ReservedInValue), 5)
+        );
+
+        // Successful lines have no column.
+        assert_eq!(column("x a=1", &strict), (None, 0));
+    }
 }
@@ -1,8 +1,10 @@
-use std::fmt;
+use std::{fmt, str::FromStr};
 
-use crate::{element::Element, keyval::KeyVal};
+use crate::{
+    element::Element, element_parser::ElementParser, keyval::KeyVal, literal::Literal, ParseError,
+};
 
-#[derive(PartialEq)]
+#[derive(PartialEq, Clone)]
 pub enum Delimiters {
     Unset,
     Comma,
@@ -13,8 +15,8 @@ impl Delimiters {
     pub fn value(&self) -> u8 {
         match *self {
             Delimiters::Unset => 0,
-            Delimiters::Comma => ',' as u8,
-            Delimiters::Space => ' ' as u8,
+            Delimiters::Comma => b',',
+            Delimiters::Space => b' ',
         }
     }
 }
@@ -23,10 +25,12 @@ impl Delimiters {
 /// YES spec.
 /// - [Elements::Standard] are elements whose purpose is user-defined.
 /// - [Elements::Attribute] are elements which will be associated automatically
-///     with the next valid [Elements::Standard] element. Attributes stack.
+///   with the next valid [Elements::Standard] element. Attributes stack.
 /// - [Elements::Global] are elements which will be hoisted to the top of the
-///     parsed document result and should impact the document globally.
+///   parsed document result and should impact the document globally.
 /// - [Elements::Comment] is documentation put in place by a tool or person.
+/// - [Elements::Blank] is a line with nothing on it. It is only produced when
+///   [crate::options::ParserOptions::blank_lines] is true.
 ///
 /// Typically the parser is responsible for assembling these variants.
 /// See the implemenation for ways to construct a new variant.
@@ -38,6 +42,7 @@ pub enum Elements {
     Attribute(Element),
     Global(Element),
     Comment(Element),
+    Blank,
 }
 
 impl Elements {
@@ -67,19 +72,122 @@ impl Elements {
         Elements::Comment(Element::new(message))
     }
 
+    /// Returns the text of an [Elements::Comment] without its surrounding
+    /// whitespace for display. Otherwise [None]. See [Elements::comment_text].
+    pub fn comment_trimmed(&self) -> Option<&str> {
+        self.comment_text().map(str::trim)
+    }
+
+    /// Constructs a new [Elements::Standard] named [name] with one [KeyVal]
+    /// for each `(key, val)` pair in [args], in order. A [None] key is nameless.
+    ///
+    /// ```
+    /// use yes_parser::enums::Elements;
+    ///
+    /// let window = Elements::standard_from("window", &[(Some("width"), "320"), (Some("height"), "240")]);
+    /// assert_eq!(window.to_string(), "window width=320, height=240");
+    /// ```
+    pub fn standard_from(name: &str, args: &[(Option<&str>, &str)]) -> Elements {
+        Elements::Standard {
+            attrs: Vec::new(),
+            element: Elements::element_from(name, args),
+        }
+    }
+
+    /// Constructs a new [Elements::Attribute] like [Elements::standard_from].
+    pub fn attribute_from(name: &str, args: &[(Option<&str>, &str)]) -> Elements {
+        Elements::Attribute(Elements::element_from(name, args))
+    }
+
+    /// Constructs a new [Elements::Global] like [Elements::standard_from].
+    pub fn global_from(name: &str, args: &[(Option<&str>, &str)]) -> Elements {
+        Elements::Global(Elements::element_from(name, args))
+    }
+
+    fn element_from(name: &str, args: &[(Option<&str>, &str)]) -> Element {
+        let mut element = Element::new(name.to_owned());
+        for (key, val) in args {
+            element.upsert(KeyVal::new(key.map(str::to_owned), (*val).to_owned()));
+        }
+        element
+    }
+
     /// Returns a copy of the data structure [Element].
     pub fn copy(other: &Element) -> Element {
         let mut args = Vec::new();
         for kv in &other.args {
-            args.push(KeyVal::copy(&kv));
+            args.push(KeyVal::copy(kv));
         }
         Element {
             text: other.text.clone(),
             args,
+            doc: other.doc.clone(),
+        }
+    }
+
+    /// Returns the text of an [Elements::Comment] verbatim, which is the raw
+    /// remainder of the line after the [Glyphs::Hash] prefix including its
+    /// spacing. Otherwise [None]. See [Elements::comment_trimmed].
+    ///
+    /// ```
+    /// use yes_parser::{ParseResult, YesDocParser};
+    ///
+    /// let results = YesDocParser::from_string("#   spaced   comment   ", None);
+    /// let data = match &results[0] {
+    ///     ParseResult::Ok { data, .. } => data,
+    ///     _ => panic!("Element expected!"),
+    /// };
+    ///
+    /// assert_eq!(data.comment_text(), Some("   spaced   comment   "));
+    /// assert_eq!(data.comment_trimmed(), Some("spaced   comment"));
+    /// ```
+    pub fn comment_text(&self) -> Option<&str> {
+        match self {
+            Elements::Comment(data) => Some(&data.text),
+            _ => None,
+        }
+    }
+
+    /// Returns the attributes attached to an [Elements::Standard]. Other
+    /// variants never carry attributes and return an empty slice.
+    ///
+    /// ```
+    /// use yes_parser::YesDocParser;
+    ///
+    /// let results = YesDocParser::from_string("!version 1\n@hidden\n@size 2\nbox", None);
+    ///
+    /// // Globals are hoisted to the front.
+    /// let global = results[0].data().unwrap();
+    /// assert!(global.attrs().is_empty());
+    ///
+    /// let standard = results[1].data().unwrap();
+    /// let names: Vec<&str> = standard.attrs().iter().map(|a| a.text.as_str()).collect();
+    /// assert_eq!(names, vec!["hidden", "size"]);
+    /// assert!(standard.has_attr("size"));
+    /// assert_eq!(standard.attr("size").unwrap().args[0].val, "2");
+    /// assert!(standard.attr("missing").is_none());
+    /// ```
+    pub fn attrs(&self) -> &[Element] {
+        match self {
+            Elements::Standard { attrs, .. } => attrs,
+            _ => &[],
         }
     }
 
+    /// Returns true if an attribute named [name] is attached.
+    /// See [Elements::attrs].
+    pub fn has_attr(&self, name: &str) -> bool {
+        self.attr(name).is_some()
+    }
+
+    /// Returns the first attached attribute named [name], if any.
+    /// See [Elements::attrs].
+    pub fn attr(&self, name: &str) -> Option<&Element> {
+        self.attrs().iter().find(|a| a.text == name)
+    }
+
     /// Simplifies a call to the data structure [Element] by pattern matching.
+    /// [Elements::Blank] has no [Element] so this is a no-op for it.
     /// See [Element::upsert].
     pub fn upsert_keyval(&mut self, keyval: KeyVal) {
         match self {
@@ -87,6 +195,20 @@ impl Elements {
             Elements::Attribute(data) => data.upsert(keyval),
             Elements::Global(data) => data.upsert(keyval),
             Elements::Comment(data) => data.upsert(keyval),
+            Elements::Blank => {}
+        }
+    }
+
+    /// Simplifies a call to the data structure [Element] by pattern matching.
+    /// [Elements::Blank] has no [Element] so this is a no-op for it.
+    /// See [Element::append].
+    pub fn append_keyval(&mut self, keyval: KeyVal) {
+        match self {
+            Elements::Standard { element: data, .. } => data.append(keyval),
+            Elements::Attribute(data) => data.append(keyval),
+            Elements::Global(data) => data.append(keyval),
+            Elements::Comment(data) => data.append(keyval),
+            Elements::Blank => {}
         }
     }
 }
@@ -100,9 +222,15 @@ impl fmt::Display for Elements {
             Elements::Attribute(data) => (Glyphs::At, data),
             Elements::Global(data) => (Glyphs::Bang, data),
             Elements::Comment(data) => (Glyphs::Hash, data),
+            Elements::Blank => return Ok(()),
+        };
+
+        // Standard elements have no prefix to print.
+        let char_glyph = match glyph {
+            Glyphs::None => String::new(),
+            _ => (glyph.value() as char).to_string(),
         };
 
-        let char_glyph = glyph.value() as char;
         if element.args.is_empty() {
             write!(f, "{}{}", char_glyph, element.text)
         } else {
@@ -120,6 +248,53 @@ impl fmt::Display for Elements {
     }
 }
 
+impl FromStr for Elements {
+    type Err = ParseError;
+
+    /// Parses exactly one line into an [Elements] value with the default
+    /// [crate::options::ParserOptions]. The line is read as line number 1.
+    /// Input with a newline reports [ErrorCodes::Runtime] since a
+    /// continued or second line cannot be one element.
+    ///
+    /// ```
+    /// use yes_parser::enums::Elements;
+    ///
+    /// let el: Elements = "window width=320".parse().unwrap();
+    /// assert_eq!(el.to_string(), "window width=320");
+    ///
+    /// assert!("a\nb".parse::<Elements>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = |code: ErrorCodes, message: String, column: usize| ParseError {
+            line_number: 1,
+            message,
+            code,
+            source: s.to_owned(),
+            first_line_number: 1,
+            column,
+        };
+
+        if s.contains('\n') {
+            return Err(error(
+                ErrorCodes::Runtime,
+                "Expected exactly one line.".to_owned(),
+                s.find('\n').unwrap_or_default(),
+            ));
+        }
+
+        let mut parser = ElementParser::read(1, s, &Some(vec![Literal::build_quotes()]));
+
+        match (parser.error.take(), parser.element.take()) {
+            (None, Some(element)) => Ok(element),
+            (code, _) => {
+                let code = code.unwrap_or(ErrorCodes::EolNoData);
+                let message = code.values().to_owned();
+                Err(error(code, message, parser.column))
+            }
+        }
+    }
+}
+
 #[derive(PartialEq)]
 pub enum Glyphs {
     None,
@@ -128,6 +303,7 @@ pub enum Glyphs {
     Bang,
     Hash,
     Space,
+    Tab,
     Comma,
     Quote,
     Backslash,
@@ -136,44 +312,150 @@ pub enum Glyphs {
 impl Glyphs {
     pub fn value(&self) -> u8 {
         match *self {
-            Glyphs::At => '@' as u8,
-            Glyphs::Bang => '!' as u8,
-            Glyphs::Comma => ',' as u8,
-            Glyphs::Equal => '=' as u8,
-            Glyphs::Hash => '#' as u8,
+            Glyphs::At => b'@',
+            Glyphs::Bang => b'!',
+            Glyphs::Comma => b',',
+            Glyphs::Equal => b'=',
+            Glyphs::Hash => b'#',
             Glyphs::None => 0,
-            Glyphs::Quote => '"' as u8,
-            Glyphs::Space => ' ' as u8,
-            Glyphs::Backslash => '\\' as u8,
+            Glyphs::Quote => b'"',
+            Glyphs::Space => b' ',
+            Glyphs::Tab => b'\t',
+            Glyphs::Backslash => b'\\',
         }
     }
 
     pub fn from(char: u8) -> Glyphs {
         match char {
-            val if val == '@' as u8 => Glyphs::At,
-            val if val == '!' as u8 => Glyphs::Bang,
-            val if val == ',' as u8 => Glyphs::Comma,
-            val if val == '=' as u8 => Glyphs::Equal,
-            val if val == '#' as u8 => Glyphs::Hash,
-            val if val == '"' as u8 => Glyphs::Quote,
-            val if val == ' ' as u8 => Glyphs::Space,
-            val if val == '\\' as u8 => Glyphs::Backslash,
+            b'@' => Glyphs::At,
+            b'!' => Glyphs::Bang,
+            b',' => Glyphs::Comma,
+            b'=' => Glyphs::Equal,
+            b'#' => Glyphs::Hash,
+            b'"' => Glyphs::Quote,
+            b' ' => Glyphs::Space,
+            b'\t' => Glyphs::Tab,
+            b'\\' => Glyphs::Backslash,
             _ => Glyphs::None,
         }
     }
 
     /// If the input [char] is one of the spec-reserved characters,
-    /// returns true.
+    /// returns true. See [GlyphSet::RESERVED].
     pub fn is_reserved(char: u8) -> bool {
-        match Glyphs::from(char) {
-            Glyphs::At => true,
-            Glyphs::Bang => true,
-            Glyphs::Comma => true,
-            Glyphs::Equal => true,
-            Glyphs::Hash => true,
-            Glyphs::Quote => true,
-            _ => false,
-        }
+        GlyphSet::RESERVED.contains(char)
+    }
+
+    /// Returns true if the input [char] is [Glyphs::Space], [Glyphs::Tab], a
+    /// form feed, or a vertical tab. The parser treats them all the same
+    /// wherever whitespace separates or surrounds tokens, so `frame\twidth=1`
+    /// is the same as `frame width=1`.
+    ///
+    /// ```
+    /// use yes_parser::enums::Glyphs;
+    ///
+    /// assert!(Glyphs::from(b'\t') == Glyphs::Tab);
+    /// assert!(Glyphs::is_whitespace(b' '));
+    /// assert!(Glyphs::is_whitespace(b'\t'));
+    /// assert!(Glyphs::is_whitespace(0x0B));
+    /// assert!(Glyphs::is_whitespace(0x0C));
+    /// assert!(!Glyphs::is_whitespace(b','));
+    /// ```
+    pub fn is_whitespace(char: u8) -> bool {
+        matches!(char, 0x0B | 0x0C) || char == Glyphs::Space.value() || char == Glyphs::Tab.value()
+    }
+}
+
+/// [ControlChars] decides what the parser does with a line containing a
+/// control character, such as a stray NUL byte from binary-contaminated input.
+/// Every [char] for which [char::is_control] is true counts, except for the
+/// tab, form feed, vertical tab, and carriage return which are treated as
+/// whitespace.
+/// See [crate::options::ParserOptions::control_chars].
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ControlChars {
+    /// Control characters are parsed like any other character.
+    Keep,
+
+    /// Control characters are removed from the line before it is parsed.
+    Strip,
+
+    /// The line reports [ErrorCodes::ControlCharacter].
+    Reject,
+}
+
+impl ControlChars {
+    /// Returns true if [c] is a control character as described by [ControlChars].
+    pub fn is_control(c: char) -> bool {
+        c.is_control() && !matches!(c, '\t' | '\r' | '\x0B' | '\x0C')
+    }
+}
+
+/// [ErrorPlacement] decides where [crate::ParseResult::Err] results land once
+/// globals are hoisted to the front of the results.
+/// See [crate::options::ParserOptions::error_placement].
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ErrorPlacement {
+    /// Errors are sorted by line among every other non-global result.
+    Interleaved,
+
+    /// Errors are sorted by line after every other result.
+    Last,
+
+    /// Errors keep the position of their line in the original line order.
+    /// Only the other results are moved around them by the hoist.
+    OriginalOrder,
+}
+
+/// A set of bytes backed by a lookup table so that testing if a byte is in
+/// the set is a single index. The table can be built at compile time.
+///
+/// ```
+/// use yes_parser::enums::GlyphSet;
+///
+/// const PREFIXES: GlyphSet = GlyphSet::empty().with(b'@').with(b'!');
+/// assert!(PREFIXES.contains(b'@'));
+/// assert!(!PREFIXES.contains(b'#'));
+/// ```
+#[derive(Clone, PartialEq)]
+pub struct GlyphSet([bool; 256]);
+
+impl GlyphSet {
+    /// The spec-reserved characters. See [Glyphs::is_reserved].
+    pub const RESERVED: GlyphSet = GlyphSet::empty()
+        .with(b'@')
+        .with(b'!')
+        .with(b',')
+        .with(b'=')
+        .with(b'#')
+        .with(b'"');
+
+    /// Constructs a [GlyphSet] which contains no bytes.
+    pub const fn empty() -> GlyphSet {
+        GlyphSet([false; 256])
+    }
+
+    /// Returns [self] with [char] added to the set.
+    pub const fn with(mut self, char: u8) -> GlyphSet {
+        self.0[char as usize] = true;
+        self
+    }
+
+    /// Returns [self] with [char] removed from the set.
+    pub const fn without(mut self, char: u8) -> GlyphSet {
+        self.0[char as usize] = false;
+        self
+    }
+
+    /// Returns true if [char] is in the set.
+    pub const fn contains(&self, char: u8) -> bool {
+        self.0[char as usize]
+    }
+}
+
+impl Default for GlyphSet {
+    fn default() -> Self {
+        GlyphSet::RESERVED
     }
 }
 
@@ -186,11 +468,18 @@ impl Glyphs {
 pub enum ErrorCodes {
     BadTokenPosAttribute,
     BadTokenPosBang,
+    BadTokenPosEqual,
     EolNoData,
     EolMissingElement,
     EolMissingAttribute,
     EolMissingGlobal,
     UnterminatedQuote,
+    DuplicateKey,
+    UnbalancedLiteral,
+    LineTooLong,
+    ReservedInValue,
+    ControlCharacter,
+    TooManyArgs,
     Runtime,
 }
 
@@ -200,12 +489,70 @@ impl ErrorCodes {
         match *self {
             ErrorCodes::BadTokenPosAttribute => "Element using attribute prefix out-of-place.",
             ErrorCodes::BadTokenPosBang => "Element using global prefix out-of-place.",
+            ErrorCodes::BadTokenPosEqual => "Keyvalue using equal glyph out-of-place.",
             ErrorCodes::EolNoData => "Nothing to parse (EOL).",
             ErrorCodes::EolMissingElement => "Missing element name (EOL).",
             ErrorCodes::EolMissingAttribute => "Missing attribute name (EOL).",
             ErrorCodes::EolMissingGlobal => "Missing global identifier (EOL).",
             ErrorCodes::UnterminatedQuote => "Missing end quote in expression.",
+            ErrorCodes::DuplicateKey => "Keyvalue using a key which was already assigned.",
+            ErrorCodes::UnbalancedLiteral => "Missing end literal in expression.",
+            ErrorCodes::LineTooLong => "Line exceeds the maximum length.",
+            ErrorCodes::ReservedInValue => "Value using a reserved glyph without quotes.",
+            ErrorCodes::ControlCharacter => "Line contains a control character.",
+            ErrorCodes::TooManyArgs => "Element has more arguments than allowed.",
             ErrorCodes::Runtime => "Unexpected runtime error.",
         }
     }
+
+    /// Return the name of this code's variant e.g. `"EolNoData"`.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            ErrorCodes::BadTokenPosAttribute => "BadTokenPosAttribute",
+            ErrorCodes::BadTokenPosBang => "BadTokenPosBang",
+            ErrorCodes::BadTokenPosEqual => "BadTokenPosEqual",
+            ErrorCodes::EolNoData => "EolNoData",
+            ErrorCodes::EolMissingElement => "EolMissingElement",
+            ErrorCodes::EolMissingAttribute => "EolMissingAttribute",
+            ErrorCodes::EolMissingGlobal => "EolMissingGlobal",
+            ErrorCodes::UnterminatedQuote => "UnterminatedQuote",
+            ErrorCodes::DuplicateKey => "DuplicateKey",
+            ErrorCodes::UnbalancedLiteral => "UnbalancedLiteral",
+            ErrorCodes::LineTooLong => "LineTooLong",
+            ErrorCodes::ReservedInValue => "ReservedInValue",
+            ErrorCodes::ControlCharacter => "ControlCharacter",
+            ErrorCodes::TooManyArgs => "TooManyArgs",
+            ErrorCodes::Runtime => "Runtime",
+        }
+    }
+}
+
+impl fmt::Display for ErrorCodes {
+    /// Prints the message associated with this code.
+    /// See [ErrorCodes::values].
+    ///
+    /// ```
+    /// use yes_parser::enums::ErrorCodes;
+    ///
+    /// let code = ErrorCodes::EolNoData;
+    /// assert_eq!(format!("{}", code), "Nothing to parse (EOL).");
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.values())
+    }
+}
+
+impl fmt::Debug for ErrorCodes {
+    /// Prints the name of this code's variant.
+    /// See [ErrorCodes::name].
+    ///
+    /// ```
+    /// use yes_parser::enums::ErrorCodes;
+    ///
+    /// let code = ErrorCodes::EolNoData;
+    /// assert_eq!(format!("{:?}", code), "EolNoData");
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
 }
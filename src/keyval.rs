@@ -1,58 +1,332 @@
-use crate::{enums::Glyphs, utils::StringUtils};
+use std::fmt;
+
+use crate::{
+    enums::Delimiters,
+    utils::{needs_quoting, str_quote},
+};
 
 pub struct KeyVal {
     pub key: Option<String>,
     pub val: String,
-    key_contains_space: bool,
-    value_contains_space: bool,
+    key_needs_quotes: bool,
+    value_needs_quotes: bool,
+    literal: Option<String>,
 }
 
 impl KeyVal {
     pub fn new(key: Option<String>, val: String) -> KeyVal {
         KeyVal {
-            key_contains_space: match key {
+            key_needs_quotes: match key {
                 None => false,
-                Some(ref k) => k.find(|x| x == Glyphs::Space.value() as char).is_some(),
+                // An empty key would otherwise read as a nameless value.
+                Some(ref k) => k.is_empty() || needs_quoting(k, Delimiters::Space),
             },
-            value_contains_space: val.find(|x| x == Glyphs::Space.value() as char).is_some(),
+            // An empty value would otherwise be skipped or take the next one.
+            value_needs_quotes: val.is_empty() || needs_quoting(&val, Delimiters::Space),
             key,
             val,
+            literal: None,
         }
     }
 
     pub fn copy(other: &KeyVal) -> KeyVal {
-        KeyVal::new(other.key.clone(), other.val.clone())
+        KeyVal::new(other.key.clone(), other.val.clone()).with_literal_name(other.literal.clone())
+    }
+
+    /// Returns [self] tagged with the [crate::literal::Literal::name] of the
+    /// literal which captured [KeyVal::val]. See [KeyVal::literal_name].
+    pub fn with_literal_name(mut self, name: Option<String>) -> KeyVal {
+        self.literal = name;
+        self
+    }
+
+    /// Returns the [crate::literal::Literal::name] of the literal whose span
+    /// is the whole value, e.g. `date` for a value `#2024-01-01#` captured by
+    /// a literal `#...#` with that name. Otherwise [None], including for
+    /// unnamed literals such as quotes.
+    pub fn literal_name(&self) -> Option<&str> {
+        self.literal.as_deref()
     }
 
     pub fn is_nameless(&self) -> bool {
-        self.key == None
+        self.key.is_none()
+    }
+
+    /// Returns [KeyVal::key] as a borrowed [str], if any.
+    pub fn key_str(&self) -> Option<&str> {
+        self.key.as_deref()
+    }
+
+    /// Returns [KeyVal::val] as a borrowed [str].
+    pub fn val_str(&self) -> &str {
+        &self.val
+    }
+
+    /// Returns true if [KeyVal::key] is [other] ignoring ASCII case, so
+    /// `Width` matches `width`. Only `A-Z` and `a-z` are folded, so `É` and
+    /// `é` differ. A nameless [KeyVal] never matches. Nothing is allocated.
+    pub fn key_eq_ignore_case(&self, other: &str) -> bool {
+        self.key
+            .as_deref()
+            .is_some_and(|key| key.eq_ignore_ascii_case(other))
+    }
+
+    /// Returns true if [KeyVal::val] is [other] ignoring ASCII case, so
+    /// `Fullscreen` matches `fullscreen`. See [KeyVal::key_eq_ignore_case].
+    pub fn val_eq_ignore_case(&self, other: &str) -> bool {
+        self.val.eq_ignore_ascii_case(other)
+    }
+
+    /// Splits [KeyVal::val] on [sep] and trims the whitespace around each
+    /// piece, so `10; 20;30` yields `["10", "20", "30"]`. [KeyVal::key] is
+    /// never split.
+    ///
+    /// Empty pieces are kept so that positions are preserved: `a;;c` yields
+    /// `["a", "", "c"]` and `;a;` yields `["", "a", ""]`. An empty value
+    /// yields no pieces.
+    pub fn split_value(&self, sep: char) -> Vec<&str> {
+        if self.val.is_empty() {
+            return Vec::new();
+        }
+
+        self.val.split(sep).map(str::trim).collect()
+    }
+
+    /// Returns [self] with [KeyVal::val] replaced by the result of [f], e.g.
+    /// to trim or lowercase it. Whether the value must be quoted when
+    /// displayed is computed again. [KeyVal::literal_name] is kept.
+    pub fn map_val(self, f: impl FnOnce(String) -> String) -> KeyVal {
+        KeyVal::new(self.key, f(self.val)).with_literal_name(self.literal)
+    }
+
+    /// Returns [self] with [KeyVal::key] replaced by the result of [f]. A
+    /// [None] result makes the [KeyVal] nameless. Whether the key must be
+    /// quoted when displayed is computed again. See [KeyVal::map_val].
+    pub fn map_key(self, f: impl FnOnce(Option<String>) -> Option<String>) -> KeyVal {
+        KeyVal::new(f(self.key), self.val).with_literal_name(self.literal)
+    }
+
+    /// Returns the key and value verbatim for display or logging.
+    /// Unlike [fmt::Display], quotes are never added.
+    pub fn display_raw(&self) -> String {
+        match self.key {
+            Some(ref k) => format!("{}={}", k, self.val),
+            None => self.val.clone(),
+        }
     }
 }
 
-impl ToString for KeyVal {
-    fn to_string(&self) -> String {
-        let v = match self.value_contains_space {
-            true => {
-                let mut clone = self.val.clone();
-                clone.quote();
-                clone
-            }
+impl fmt::Display for KeyVal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let v = match self.value_needs_quotes {
+            true => str_quote(&self.val),
             false => self.val.clone(),
         };
 
         if self.is_nameless() {
-            return format!("{}", v);
+            return write!(f, "{}", v);
         }
 
-        let k = match self.key_contains_space {
-            true => {
-                let mut clone = self.key.clone().unwrap();
-                clone.quote();
-                clone
-            }
+        let k = match self.key_needs_quotes {
+            true => str_quote(self.key.as_ref().unwrap()),
             false => self.key.clone().unwrap(),
         };
 
-        format!("{}={}", k, v)
+        write!(f, "{}={}", k, v)
+    }
+}
+
+/// With the `serde` feature, a [KeyVal] is serialized as `{key, val}` where
+/// `key` is null for a nameless value. The quoting flags are not written and
+/// are recomputed by [KeyVal::new] on deserialize. [KeyVal::literal_name] is
+/// not kept.
+#[cfg(feature = "serde")]
+impl serde::Serialize for KeyVal {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("KeyVal", 2)?;
+        state.serialize_field("key", &self.key)?;
+        state.serialize_field("val", &self.val)?;
+        state.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for KeyVal {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        #[serde(rename = "KeyVal")]
+        struct Fields {
+            #[serde(default)]
+            key: Option<String>,
+            val: String,
+        }
+
+        let fields = Fields::deserialize(deserializer)?;
+        Ok(KeyVal::new(fields.key, fields.val))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{enums::Elements, keyval::KeyVal, YesDocParser};
+
+    /// Parses [content] and returns the key and value of every arg of the
+    /// first element.
+    fn parse_args(content: &str) -> Vec<(Option<String>, String)> {
+        let results = YesDocParser::from_string(content, None);
+        match results[0].data() {
+            Some(Elements::Standard { element, .. }) => element
+                .args
+                .iter()
+                .map(|kv| (kv.key.clone(), kv.val.clone()))
+                .collect(),
+            _ => panic!("Standard element expected!"),
+        }
+    }
+
+    #[test]
+    fn roundtrip() {
+        for content in [
+            // Value with spaces.
+            "x v=\"a b\"",
+            // Key with spaces.
+            "x \"full name\"=c",
+            // Value with a comma, with and without spaces.
+            "x v=\"a, b\" w=\"c,d\"",
+            // Nameless value with an equal glyph.
+            "x \"a=b\" c",
+            // Value which is already quoted.
+            "x v=\"q\"",
+            // Key with parenthesis from a macro.
+            "x teardown_textbox(tb)=\"call textbox_teardown\"",
+            // Value with surrounding spaces.
+            "x v=\"  padded \"",
+            // Value with a reserved prefix glyph.
+            "x v=\"x!y\" \"#tag\"",
+        ] {
+            let args = parse_args(content);
+
+            let written: Vec<String> = args
+                .iter()
+                .map(|(k, v)| KeyVal::new(k.clone(), v.clone()).to_string())
+                .collect();
+
+            let reparsed = parse_args(&format!("x {}", written.join(", ")));
+            assert_eq!(args, reparsed, "{} did not roundtrip", content);
+        }
+    }
+
+    #[test]
+    fn display_raw() {
+        let kv = KeyVal::new(Some("full name".to_owned()), "John Doe".to_owned());
+        assert_eq!(kv.to_string(), "\"full name\"=\"John Doe\"");
+        assert_eq!(kv.display_raw(), "full name=John Doe");
+        assert_eq!(kv.key_str(), Some("full name"));
+        assert_eq!(kv.val_str(), "John Doe");
+
+        let kv = KeyVal::new(None, "hello world".to_owned());
+        assert_eq!(kv.display_raw(), "hello world");
+        assert_eq!(kv.key_str(), None);
+    }
+
+    #[test]
+    fn split_value() {
+        let kv = |val: &str| KeyVal::new(Some("a;b".to_owned()), val.to_owned());
+
+        assert_eq!(kv("a;b;c").split_value(';'), vec!["a", "b", "c"]);
+        assert_eq!(kv("a;;c").split_value(';'), vec!["a", "", "c"]);
+        assert_eq!(kv(";a;").split_value(';'), vec!["", "a", ""]);
+        assert_eq!(kv("10; 20 ;30").split_value(';'), vec!["10", "20", "30"]);
+        assert_eq!(kv("single").split_value(';'), vec!["single"]);
+        assert!(kv("").split_value(';').is_empty());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn quoting() {
+        let kv = |val: &str| KeyVal::new(Some("v".to_owned()), val.to_owned()).to_string();
+
+        assert_eq!(kv("a,b"), "v=\"a,b\"");
+        assert_eq!(kv(" a"), "v=\" a\"");
+        assert_eq!(kv("a=b"), "v=\"a=b\"");
+        assert_eq!(kv("plain"), "v=plain");
+
+        // Quotes inside the value already keep its glyphs together.
+        assert_eq!(kv("b\"a,b\""), "v=b\"a,b\"");
+
+        // Empty keys and values are written as quotes.
+        assert_eq!(kv(""), "v=\"\"");
+        assert_eq!(KeyVal::new(None, String::new()).to_string(), "\"\"");
+        assert_eq!(
+            KeyVal::new(Some(String::new()), "a".to_owned()).to_string(),
+            "\"\"=a"
+        );
+    }
+
+    #[test]
+    fn eq_ignore_case() {
+        let kv = KeyVal::new(Some("Mode".to_owned()), "Fullscreen".to_owned());
+        assert!(kv.key_eq_ignore_case("mode"));
+        assert!(kv.val_eq_ignore_case("fullscreen"));
+        assert!(kv.val_eq_ignore_case("FULLSCREEN"));
+        assert!(!kv.val_eq_ignore_case("fullscreen2"));
+
+        // Only ASCII is folded.
+        let kv = KeyVal::new(None, "École".to_owned());
+        assert!(!kv.val_eq_ignore_case("éCOLE"));
+        assert!(kv.val_eq_ignore_case("École"));
+        assert!(!kv.key_eq_ignore_case("école"));
+    }
+
+    #[test]
+    fn quoted_keys() {
+        // A quoted key without spaces is unquoted and written back bare.
+        let args = parse_args("x \"width\"=320");
+        assert_eq!(args, vec![(Some("width".to_owned()), "320".to_owned())]);
+
+        let written = KeyVal::new(args[0].0.clone(), args[0].1.clone()).to_string();
+        assert_eq!(written, "width=320");
+        assert_eq!(parse_args(&format!("x {}", written)), args);
+
+        // A key with spaces keeps its quotes when written back.
+        let args = parse_args("x \"full name\"=John");
+        assert_eq!(
+            args,
+            vec![(Some("full name".to_owned()), "John".to_owned())]
+        );
+
+        let written = KeyVal::new(args[0].0.clone(), args[0].1.clone()).to_string();
+        assert_eq!(written, "\"full name\"=John");
+        assert_eq!(parse_args(&format!("x {}", written)), args);
+    }
+
+    #[test]
+    fn map_val_and_key() {
+        let kv = KeyVal::new(Some("name".to_owned()), "ab".to_owned());
+        assert_eq!(kv.to_string(), "name=ab");
+
+        // Introducing a space quotes the value and key.
+        let kv = kv.map_val(|val| val.replace("b", " b"));
+        assert_eq!(kv.to_string(), "name=\"a b\"");
+
+        let kv = kv.map_key(|key| key.map(|key| format!("full {}", key)));
+        assert_eq!(kv.to_string(), "\"full name\"=\"a b\"");
+
+        // Removing the space no longer needs quotes.
+        let kv = kv
+            .map_val(|val| val.replace(' ', ""))
+            .map_key(|key| key.map(|key| key.replace(' ', "_")));
+        assert_eq!(kv.to_string(), "full_name=ab");
+
+        let kv = kv.map_key(|_| None);
+        assert!(kv.is_nameless());
+        assert_eq!(kv.to_string(), "ab");
+
+        let kv = KeyVal::new(None, "x".to_owned())
+            .with_literal_name(Some("date".to_owned()))
+            .map_val(|val| val.to_uppercase());
+        assert_eq!(kv.val, "X");
+        assert_eq!(kv.literal_name(), Some("date"));
+    }
+}
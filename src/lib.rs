@@ -9,38 +9,72 @@
 //! `begin` and `end` tokens. Both entry-points will append the result from
 //! `List::build_quotes()` regardless if any custom literals are also provided.
 //!
+//! The results can be wrapped in a `Document` via `Document::from_file`
+//! or `Document::from_string` for document-wide queries such as
+//! `Document::diff`.
+//!
 //! Literals instruct the parser which span of characters, called a token,
 //! will be considered when finding the next key-value pair. This implies,
 //! by default, that quoted strings can be parsed correctly so that they can
 //! be key or a value even if they contain reserved symbols.
 use std::{
-    cmp::Ordering,
+    fmt,
     fs::File,
-    io::{BufRead, BufReader},
+    io::{self, BufRead, BufReader},
 };
 
+use document::Document;
 use element::Element;
 use element_parser::ElementParser;
-use enums::{Elements, ErrorCodes, Glyphs};
+use enums::{Elements, ErrorCodes, ErrorPlacement, Glyphs};
+use lint::Diagnostic;
 use literal::Literal;
+use options::{FormatOptions, ParserOptions};
 
+#[cfg(feature = "binary")]
+pub mod binary;
+#[cfg(feature = "serde")]
+pub mod de;
+pub mod document;
 pub mod element;
 pub mod element_parser;
 pub mod enums;
 pub mod keyval;
+pub mod lint;
 pub mod literal;
+pub mod options;
+#[cfg(feature = "async")]
+pub mod stream;
 pub mod utils;
+pub mod visitor;
 
 /// Custom [Result] enum with both variants having a field `line_number`.
 pub enum ParseResult {
     Ok {
         line_number: usize,
         data: Elements,
+
+        /// The indentation of the line in spaces when
+        /// [ParserOptions::indent_sensitive] is true. Otherwise [None].
+        depth: Option<usize>,
     },
     Err {
         line_number: usize,
         message: String,
         code: ErrorCodes,
+
+        /// The logical line which failed to parse. For multiline elements
+        /// this is the line joined from every continued physical line.
+        source: String,
+
+        /// The physical line number the logical line began on. This is equal
+        /// to `line_number` unless the element spanned multiple lines.
+        first_line_number: usize,
+
+        /// The byte index into the trimmed logical line of the glyph which
+        /// caused the error, e.g. the `!` of `@!foo`. Zero if no single glyph
+        /// is at fault. See [ElementParser::column].
+        column: usize,
     },
 }
 
@@ -53,6 +87,9 @@ impl ParseResult {
             line_number,
             message: code.values().to_owned(),
             code,
+            source: String::new(),
+            first_line_number: line_number,
+            column: 0,
         }
     }
 
@@ -66,113 +103,444 @@ impl ParseResult {
             line_number,
             message,
             code: ErrorCodes::Runtime,
+            source: String::new(),
+            first_line_number: line_number,
+            column: 0,
+        }
+    }
+
+    /// Returns the parsed [Elements] if [self] is [ParseResult::Ok].
+    /// Otherwise [None].
+    ///
+    /// ```
+    /// use yes_parser::{enums::Elements, YesDocParser};
+    ///
+    /// let results = YesDocParser::from_string("!version 1.0\n@!bad", None);
+    ///
+    /// match results[0].data() {
+    ///     Some(Elements::Global(element)) => assert_eq!(element.text, "version"),
+    ///     _ => panic!("Global expected!"),
+    /// }
+    ///
+    /// assert!(results[1].data().is_none());
+    /// ```
+    pub fn data(&self) -> Option<&Elements> {
+        match self {
+            ParseResult::Ok { data, .. } => Some(data),
+            ParseResult::Err { .. } => None,
+        }
+    }
+
+    /// Returns true if [self] is [ParseResult::Err].
+    pub fn is_err(&self) -> bool {
+        matches!(self, ParseResult::Err { .. })
+    }
+
+    /// Returns the line number of either variant.
+    pub fn line_number(&self) -> usize {
+        match self {
+            ParseResult::Ok { line_number, .. } => *line_number,
+            ParseResult::Err { line_number, .. } => *line_number,
+        }
+    }
+
+    /// If [self] is [ParseResult::Err], then this sets the logical line
+    /// [source] which began on [first_line_number] and returns [self].
+    /// Otherwise, this is a no-op.
+    pub fn with_source(mut self, first_line_number: usize, source: &str) -> ParseResult {
+        if let ParseResult::Err {
+            source: ref mut s,
+            first_line_number: ref mut n,
+            ..
+        } = self
+        {
+            *s = source.to_owned();
+            *n = first_line_number;
         }
+
+        self
+    }
+
+    /// Sets [ParseResult::Err::column] if [self] is [ParseResult::Err].
+    /// Otherwise [self] is returned as-is.
+    pub fn with_column(mut self, column: usize) -> ParseResult {
+        if let ParseResult::Err {
+            column: ref mut c, ..
+        } = self
+        {
+            *c = column;
+        }
+
+        self
     }
 }
 
+/// The owned fields of a [ParseResult::Err] once separated from the
+/// successful results. See [crate::document::Document::into_parts].
+#[derive(Debug)]
+pub struct ParseError {
+    pub line_number: usize,
+    pub message: String,
+    pub code: ErrorCodes,
+    pub source: String,
+    pub first_line_number: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for ParseError {
+    /// Prints the line number and message, e.g. `#3: Nothing to parse (EOL).`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}: {}", self.line_number, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
 /// The entry-point for parsing YES documents and scriplets.
 /// It is responsible for tracking the total number of lines fed,
 /// the line being built (in the event of multi-lines),
-/// the attributes for the next standard element, and collecting
-/// the results of the [ElementParser::read] routine.
+/// the attributes and doc comments for the next standard element, the sticky
+/// attributes for all following standard elements, and collecting the results of
+/// the [ElementParser::read] routine.
 pub struct YesDocParser {
     total_lines: usize,
     building_line: Option<String>,
-    attrs: Vec<Element>,
+    building_line_number: usize,
+    attrs: Vec<(usize, Element)>,
+    sticky_attrs: Vec<Element>,
+    docs: Vec<ParseResult>,
     results: Vec<ParseResult>,
+    trailing: Option<String>,
+    truncated: Vec<usize>,
+    recovered: Vec<usize>,
+    options: ParserOptions,
+    element_parser: ElementParser,
 }
 
 impl YesDocParser {
     /// Returns a list of [ParserResult] values read from an input [file].
-    pub fn from_file(file: &File, literals: Option<Vec<Literal>>) -> Vec<ParseResult> {
-        let reader = BufReader::new(file);
+    /// Returns the first [io::Error] if a line cannot be read, such as a line
+    /// which is not valid UTF-8.
+    pub fn from_file(
+        file: &File,
+        literals: Option<Vec<Literal>>,
+    ) -> Result<Vec<ParseResult>, io::Error> {
+        YesDocParser::from_file_with_options(file, literals, ParserOptions::default())
+    }
 
-        let mut parser = YesDocParser {
-            total_lines: 0,
-            building_line: None,
-            attrs: Vec::new(),
-            results: Vec::new(),
-        };
+    /// A variation of [YesDocParser::from_file] which parses with [options].
+    pub fn from_file_with_options(
+        file: &File,
+        literals: Option<Vec<Literal>>,
+        options: ParserOptions,
+    ) -> Result<Vec<ParseResult>, io::Error> {
+        Ok(YesDocParser::read_file(file, literals, options)?.results)
+    }
 
-        let mut literals = match literals {
-            Some(ref custom) => custom.clone(),
-            None => Vec::new(),
-        };
+    /// Returns a list of [ParserResult] values read from [body].
+    pub fn from_string(body: &str, literals: Option<Vec<Literal>>) -> Vec<ParseResult> {
+        YesDocParser::from_string_with_options(body, literals, ParserOptions::default())
+    }
 
-        literals.insert(0, Literal::build_quotes());
+    /// A variation of [YesDocParser::from_string] which parses with [options].
+    pub fn from_string_with_options(
+        body: &str,
+        literals: Option<Vec<Literal>>,
+        options: ParserOptions,
+    ) -> Vec<ParseResult> {
+        YesDocParser::read_string(body, literals, options).results
+    }
 
-        let literals = Some(literals);
+    /// Returns an [Iterator] of [ParseResult] values read from [reader] one
+    /// logical line at a time, so a large document is never held in memory.
+    ///
+    /// Lines are fed through the same core as [YesDocParser::from_string] so
+    /// multi-lines, attributes, and doc comments behave the same. Unlike the
+    /// other readers, results are yielded in the order they are read and
+    /// [Elements::Global] elements are not hoisted to the front. Reading stops
+    /// at [ParserOptions::end_marker] or after the first I/O error, which is
+    /// yielded as a [ParseResult::Err]. See [YesDocParser::from_async_reader]
+    /// with the `async` feature.
+    pub fn iter_reader<R: BufRead>(
+        reader: R,
+        literals: Option<Vec<Literal>>,
+    ) -> impl Iterator<Item = ParseResult> {
+        YesDocParser::iter_reader_with_options(reader, literals, ParserOptions::default())
+    }
 
-        for line in reader.lines() {
-            parser.process(&mut line.unwrap(), &literals);
-        }
+    /// A variation of [YesDocParser::iter_reader] which parses with [options].
+    pub fn iter_reader_with_options<R: BufRead>(
+        reader: R,
+        literals: Option<Vec<Literal>>,
+        options: ParserOptions,
+    ) -> impl Iterator<Item = ParseResult> {
+        let mut parser = YesDocParser::new(options);
+        let literals = YesDocParser::with_quotes(literals);
+        let mut lines = Some(reader.lines());
+
+        // Each step reads one line and yields every result it completed.
+        std::iter::from_fn(move || {
+            let line = lines.as_mut()?.next();
+
+            if parser.step(line, &literals) {
+                lines = None;
+            }
 
-        parser.organize();
+            Some(std::mem::take(&mut parser.results))
+        })
+        .flatten()
+    }
 
-        parser.results
+    /// Returns a list of [ParserResult] values read from the bytes [body].
+    /// Invalid UTF-8 sequences are replaced with `U+FFFD` so this never fails
+    /// on encoding, but names and values may contain the replacement character.
+    pub fn from_bytes_lossy(body: &[u8], literals: Option<Vec<Literal>>) -> Vec<ParseResult> {
+        YesDocParser::from_bytes_lossy_with_options(body, literals, ParserOptions::default())
     }
 
-    /// Returns a list of [ParserResult] values read from [body].
-    pub fn from_string(body: &str, literals: Option<Vec<Literal>>) -> Vec<ParseResult> {
-        let mut parser = YesDocParser {
-            total_lines: 0,
-            building_line: None,
-            attrs: Vec::new(),
-            results: Vec::new(),
-        };
+    /// A variation of [YesDocParser::from_bytes_lossy] which parses with [options].
+    pub fn from_bytes_lossy_with_options(
+        body: &[u8],
+        literals: Option<Vec<Literal>>,
+        options: ParserOptions,
+    ) -> Vec<ParseResult> {
+        // A newline is never part of an invalid sequence, so converting the
+        // whole body at once is the same as converting each line.
+        YesDocParser::from_string_with_options(&String::from_utf8_lossy(body), literals, options)
+    }
+
+    /// A variation of [YesDocParser::from_string_with_options] which also
+    /// returns the non-fatal [Diagnostic] values found by
+    /// [Document::lint]. Only failures to parse are [ParseResult::Err].
+    pub fn parse_with_diagnostics(
+        body: &str,
+        literals: Option<Vec<Literal>>,
+        options: ParserOptions,
+    ) -> (Vec<ParseResult>, Vec<Diagnostic>) {
+        let doc = Document::from_string_with_options(body, literals, options);
+        let diagnostics = doc.lint();
+        (doc.results, diagnostics)
+    }
 
-        let mut literals = match literals {
-            Some(ref custom) => custom.clone(),
-            None => Vec::new(),
+    /// Renders [results] back into YES source with the default
+    /// [FormatOptions]. Attributes are written directly above the standard
+    /// element they apply to, and names, keys, and values are quoted where
+    /// needed so that parsing the source returns the same elements. Results
+    /// which are [ParseResult::Err] are written as their original source.
+    /// See [Document::format] for more control.
+    ///
+    /// ```
+    /// use yes_parser::YesDocParser;
+    ///
+    /// let results = YesDocParser::from_string("@hidden\nlabel   text=\"a b\"", None);
+    /// assert_eq!(
+    ///     YesDocParser::to_yes_string(&results),
+    ///     "@hidden\nlabel text=\"a b\"\n"
+    /// );
+    /// ```
+    pub fn to_yes_string(results: &[ParseResult]) -> String {
+        Document::format_results(results, FormatOptions::default())
+    }
+
+    /// Parses [body] in two stages so that the [Elements::Global] elements can
+    /// decide how the rest of the document is parsed, e.g. `!version` gating
+    /// new features.
+    ///
+    /// The first stage parses [body] with [literals] and returns the hoisted
+    /// global [Element]s in the order they were entered. The second stage is
+    /// the returned closure which parses all of [body] again with the
+    /// [Literal]s and [ParserOptions] chosen by the caller from those globals.
+    /// See [YesDocParser::organize].
+    #[allow(clippy::type_complexity)]
+    pub fn parse_globals_first(
+        body: &str,
+        literals: Option<Vec<Literal>>,
+    ) -> (
+        Vec<Element>,
+        impl FnOnce(Option<Vec<Literal>>, ParserOptions) -> Vec<ParseResult> + '_,
+    ) {
+        // Globals are hoisted to the front of the results.
+        let globals = YesDocParser::from_string(body, literals)
+            .into_iter()
+            .map_while(|result| match result {
+                ParseResult::Ok {
+                    data: Elements::Global(element),
+                    ..
+                } => Some(element),
+                _ => None,
+            })
+            .collect();
+
+        let parse = move |literals, options| {
+            YesDocParser::from_string_with_options(body, literals, options)
         };
 
-        literals.insert(0, Literal::build_quotes());
+        (globals, parse)
+    }
 
-        let literals = Some(literals);
+    /// Reads every line of [file] and returns the finished parser.
+    /// See [YesDocParser::from_file_with_options].
+    pub(crate) fn read_file(
+        file: &File,
+        literals: Option<Vec<Literal>>,
+        options: ParserOptions,
+    ) -> Result<YesDocParser, io::Error> {
+        YesDocParser::read_lines(BufReader::new(file).lines(), literals, options)
+    }
 
-        for line in body.split("\n") {
-            parser.process(&mut String::from(line), &literals);
+    /// Reads every line of [body] and returns the finished parser.
+    /// See [YesDocParser::from_string_with_options].
+    pub(crate) fn read_string(
+        body: &str,
+        literals: Option<Vec<Literal>>,
+        options: ParserOptions,
+    ) -> YesDocParser {
+        // Strip the carriage return of a CRLF line ending the same way
+        // [BufRead::lines] does for [YesDocParser::read_file].
+        let lines = body
+            .split("\n")
+            .map(|line| Ok(String::from(line.strip_suffix('\r').unwrap_or(line))));
+
+        match YesDocParser::read_lines(lines, literals, options) {
+            Ok(parser) => parser,
+            Err(_) => unreachable!("Lines of a string are never an Err."),
         }
+    }
+
+    /// Feeds every line of [lines] to a new parser and returns the finished
+    /// parser. Stops at the first [io::Error], including one in the trailing
+    /// content after [ParserOptions::end_marker], and returns it instead.
+    fn read_lines<I>(
+        mut lines: I,
+        literals: Option<Vec<Literal>>,
+        options: ParserOptions,
+    ) -> Result<YesDocParser, io::Error>
+    where
+        I: Iterator<Item = Result<String, io::Error>>,
+    {
+        let mut parser = YesDocParser::new(options);
+
+        let literals = YesDocParser::with_quotes(literals);
+
+        while let Some(line) = lines.next() {
+            let mut line = line?;
+
+            if parser.is_end_marker(&line) {
+                let rest = lines.collect::<Result<Vec<String>, io::Error>>()?;
+                parser.trailing = Some(rest.join("\n"));
+                break;
+            }
 
+            parser.process(&mut line, &literals);
+        }
+
+        parser.flush_docs();
         parser.organize();
 
-        parser.results
+        Ok(parser)
+    }
+
+    /// Returns the custom [literals], if any, preceded by
+    /// [Literal::build_quotes] which is always passed into the parser.
+    fn with_quotes(literals: Option<Vec<Literal>>) -> Option<Vec<Literal>> {
+        let mut literals = literals.unwrap_or_default();
+
+        literals.insert(0, Literal::build_quotes());
+
+        Some(literals)
+    }
+
+    /// Returns true if [line] is exactly [ParserOptions::end_marker].
+    fn is_end_marker(&self, line: &str) -> bool {
+        self.options.end_marker.as_deref() == Some(line)
+    }
+
+    /// Feeds one [line] read from a stream of lines. Returns true when the
+    /// stream is finished because [line] is [None], an [io::Error] which is
+    /// pushed as a [ParseResult::Err], or [ParserOptions::end_marker].
+    /// Pending doc comments are flushed once the stream is finished.
+    fn step(&mut self, line: Option<io::Result<String>>, literals: &Option<Vec<Literal>>) -> bool {
+        match line {
+            Some(Ok(mut line)) if !self.is_end_marker(&line) => {
+                self.process(&mut line, literals);
+                false
+            }
+            Some(Err(error)) => {
+                self.flush_docs();
+                let line_number = self.total_lines + 1;
+                self.results
+                    .push(ParseResult::custom_error(line_number, error.to_string()));
+                true
+            }
+            _ => {
+                self.flush_docs();
+                true
+            }
+        }
+    }
+
+    /// Constructs a new [YesDocParser] with no lines fed yet.
+    fn new(options: ParserOptions) -> YesDocParser {
+        YesDocParser {
+            total_lines: 0,
+            building_line: None,
+            building_line_number: 0,
+            attrs: Vec::new(),
+            sticky_attrs: Vec::new(),
+            docs: Vec::new(),
+            results: Vec::new(),
+            trailing: None,
+            truncated: Vec::new(),
+            recovered: Vec::new(),
+            options,
+            element_parser: ElementParser::new(),
+        }
     }
 
     /// Hoist globals to the top of the list in order they were entered.
     /// This makes it easier to use the results when all [Elements::Global]
     /// elements are at the front of the result set and can be applied before
     /// other elements are read by the end-user.
+    ///
+    /// Errors are placed by [ParserOptions::error_placement].
     fn organize(&mut self) {
-        self.results.sort_by(|a, b| {
-            let (a, a_is_global) = match a {
-                ParseResult::Ok { line_number, data } => (
-                    line_number,
-                    match data {
-                        Elements::Global(_) => true,
-                        _ => false,
-                    },
-                ),
-                ParseResult::Err { line_number, .. } => (line_number, false),
-            };
+        self.results.sort_by_key(ParseResult::line_number);
 
-            let (b, b_is_global) = match b {
-                ParseResult::Ok { line_number, data } => (
-                    line_number,
-                    match data {
-                        Elements::Global(_) => true,
-                        _ => false,
-                    },
-                ),
-                ParseResult::Err { line_number, .. } => (line_number, false),
-            };
+        match self.options.error_placement {
+            ErrorPlacement::Interleaved => YesDocParser::hoist_globals(&mut self.results),
+            ErrorPlacement::Last => {
+                YesDocParser::hoist_globals(&mut self.results);
+                self.results.sort_by_key(ParseResult::is_err);
+            }
+            ErrorPlacement::OriginalOrder => {
+                let mut errors: Vec<(usize, ParseResult)> = Vec::new();
+                let mut rest: Vec<ParseResult> = Vec::with_capacity(self.results.len());
+
+                for (idx, result) in self.results.drain(..).enumerate() {
+                    match result.is_err() {
+                        true => errors.push((idx, result)),
+                        false => rest.push(result),
+                    }
+                }
+
+                YesDocParser::hoist_globals(&mut rest);
 
-            match (a_is_global, b_is_global) {
-                (true, false) => Ordering::Less,
-                (false, true) => Ordering::Greater,
-                _ => a.cmp(b),
+                // Ascending indices are always in bounds once every earlier
+                // error is back in place.
+                for (idx, error) in errors {
+                    rest.insert(idx, error);
+                }
+
+                self.results = rest;
             }
-        });
+        }
+    }
+
+    /// Moves every [Elements::Global] result to the front of [results]. The
+    /// sort is stable, so globals keep their order among themselves and so
+    /// does everything else. See [Document::hoist_globals].
+    pub(crate) fn hoist_globals(results: &mut [ParseResult]) {
+        results.sort_by_key(|result| !matches!(result.data(), Some(Elements::Global(_))));
     }
 
     /// Builds a new string, [Self::building_line], from the input [line].
@@ -182,45 +550,108 @@ impl YesDocParser {
 
         let backslash = Glyphs::Backslash.value() as char;
         if line.ends_with(backslash) {
-            *line = line.replace(backslash, "");
+            // Only the trailing backslash continues the line. Any others are
+            // part of the content, e.g. a Windows path.
+            line.pop();
+
+            let building = match self.building_line {
+                Some(ref mut str) => {
+                    *str += line;
+                    str
+                }
+                None => {
+                    self.building_line_number = self.total_lines;
+                    self.building_line.insert(line.clone())
+                }
+            };
 
-            if let Some(ref mut str) = self.building_line {
-                *str += line;
-            } else {
-                self.building_line = Some(line.clone());
+            // A continuation inside of an open quote keeps the line break.
+            if self.options.quoted_newlines
+                && ElementParser::open_literal(building, literals, &self.options)
+                    == Some(Glyphs::Quote.value())
+            {
+                building.push('\n');
             }
 
             return;
-        } else if let Some(ref mut str) = self.building_line {
-            *line = str.clone() + line;
         }
 
-        self.building_line = None;
+        // The continued line is taken before parsing so that the next line
+        // always starts fresh, even if this logical line fails to parse.
+        match self.building_line.take() {
+            Some(str) => *line = str + line,
+            None => self.building_line_number = self.total_lines,
+        }
 
-        let mut element_parser = ElementParser::read(self.total_lines, line, &literals);
+        // Reuse the same parser for every line.
+        let element_parser = &mut self.element_parser;
+        element_parser.reset(self.total_lines);
+        element_parser.parse(line, literals, &self.options);
 
         if !element_parser.is_ok() {
-            self.results.push(ParseResult::error(
+            let error = ParseResult::error(
                 element_parser.line_number,
-                element_parser.error.unwrap(),
-            ));
+                element_parser.error.take().unwrap(),
+            )
+            .with_source(self.building_line_number, line)
+            .with_column(element_parser.column);
+
+            // Errors, including blank lines, break the association between
+            // pending doc comments and the next standard element.
+            self.flush_docs();
+            self.results.push(error);
             return;
         }
 
+        if element_parser.truncated {
+            self.truncated.push(element_parser.line_number);
+        }
+
+        if element_parser.recovered.is_some() {
+            self.recovered.push(element_parser.line_number);
+        }
+
         let consumed = match element_parser.element {
+            // A sticky attribute without a name is the reset directive `@@`
+            // which clears all sticky attributes.
+            Some(Elements::Attribute(ref data)) if element_parser.sticky => {
+                if data.text.is_empty() {
+                    self.sticky_attrs.clear();
+                } else {
+                    self.sticky_attrs.push(Elements::copy(data));
+                }
+                true
+            }
             Some(Elements::Attribute(ref data)) => {
-                self.attrs.push(Elements::copy(data));
+                self.attrs.push((self.total_lines, Elements::copy(data)));
                 true
             }
             Some(Elements::Standard {
                 ref mut attrs,
-                element: _,
+                ref mut element,
             }) => {
-                for a in &self.attrs {
+                // Sticky attributes were declared before the pending stack.
+                for a in &self.sticky_attrs {
+                    attrs.push(Elements::copy(a));
+                }
+
+                for (_, a) in &self.attrs {
                     attrs.push(Elements::copy(a));
                 }
 
                 self.attrs.clear();
+
+                // Pending doc comments belong to this element.
+                for result in self.docs.drain(..) {
+                    if let ParseResult::Ok {
+                        data: Elements::Comment(comment),
+                        ..
+                    } = result
+                    {
+                        element.doc.push(comment.text);
+                    }
+                }
+
                 false
             }
             _ => false,
@@ -230,18 +661,76 @@ impl YesDocParser {
             return;
         }
 
-        self.results.push(ParseResult::Ok {
+        let depth = match self.options.indent_sensitive {
+            true => Some(YesDocParser::indent_depth(line, self.options.tab_width)),
+            false => None,
+        };
+
+        let result = ParseResult::Ok {
             line_number: self.total_lines,
+            depth,
             data: element_parser
                 .element
+                .take()
                 .expect("Expected element_parser.is_ok() to signal valid elements."),
-        });
+        };
+
+        match result.data() {
+            Some(Elements::Comment(_)) if self.options.doc_comments => {
+                self.docs.push(result);
+                return;
+            }
+            Some(Elements::Standard { .. }) => {}
+            _ => self.flush_docs(),
+        }
+
+        self.results.push(result);
+    }
+
+    /// Moves any pending doc comments into [Self::results] as standalone
+    /// [Elements::Comment] results. See [ParserOptions::doc_comments].
+    fn flush_docs(&mut self) {
+        self.results.append(&mut self.docs);
+    }
+
+    /// Returns the leading whitespace of [line] measured in spaces. Each tab
+    /// character counts as [tab_width] spaces.
+    fn indent_depth(line: &str, tab_width: usize) -> usize {
+        let mut depth = 0;
+
+        for c in line.bytes() {
+            if c == Glyphs::Space.value() {
+                depth += 1;
+            } else if c == Glyphs::Tab.value() {
+                depth += tab_width;
+            } else {
+                break;
+            }
+        }
+
+        depth
     }
 }
 
+// The original tests are kept as written.
 #[cfg(test)]
+#[allow(
+    clippy::bool_assert_comparison,
+    clippy::char_lit_as_u8,
+    clippy::iter_nth,
+    clippy::iter_nth_zero,
+    clippy::unnecessary_first_then_check
+)]
 mod tests {
-    use crate::{enums::Elements, literal::Literal, ParseResult, YesDocParser};
+    use std::io::{self, Read};
+
+    use crate::{
+        enums::{Elements, ErrorCodes, ErrorPlacement, GlyphSet, Glyphs},
+        lint::{Lint, Severity},
+        literal::Literal,
+        options::ParserOptions,
+        ParseResult, YesDocParser,
+    };
 
     #[test]
     fn element_to_string() {
@@ -253,11 +742,8 @@ mod tests {
         let first = results.first();
         assert_eq!(first.is_some(), true);
 
-        let element = match &first.unwrap() {
-            ParseResult::Ok {
-                line_number: _,
-                data: Elements::Standard{element, ..},
-            } => element,
+        let element = match first.unwrap().data() {
+            Some(Elements::Standard{element, ..}) => element,
             _ => panic!("Element expected!"),
         };
 
@@ -274,11 +760,8 @@ mod tests {
         let first = results.first();
         assert_eq!(first.is_some(), true);
 
-        let element = match &first.unwrap() {
-            ParseResult::Ok {
-                line_number: _,
-                data: Elements::Global(element),
-            } => element,
+        let element = match first.unwrap().data() {
+            Some(Elements::Global(element)) => element,
             _ => panic!("Global expected!"),
         };
         assert_eq!(element.text, "macro");
@@ -303,6 +786,7 @@ mod tests {
             Some(vec![Literal {
                 begin: '[' as u8,
                 end: ']' as u8,
+                ..Default::default()
             }]),
         );
         assert_eq!(results.len(), 2);
@@ -310,11 +794,8 @@ mod tests {
         let first = results.first();
         assert_eq!(first.is_some(), true);
 
-        let element = match &first.unwrap() {
-            ParseResult::Ok {
-                line_number: _,
-                data: Elements::Standard { attrs: _, element },
-            } => element,
+        let element = match first.unwrap().data() {
+            Some(Elements::Standard { element, .. }) => element,
             _ => panic!("Standard element expected!"),
         };
 
@@ -333,11 +814,8 @@ mod tests {
         let second = results.iter().nth(1);
         assert_eq!(second.is_some(), true);
 
-        let element = match &second.unwrap() {
-            ParseResult::Ok {
-                line_number: _,
-                data: Elements::Standard { attrs: _, element },
-            } => element,
+        let element = match second.unwrap().data() {
+            Some(Elements::Standard { element, .. }) => element,
             _ => panic!("Standard element expected!"),
         };
 
@@ -354,6 +832,37 @@ mod tests {
         assert_eq!(arg2.val.len(), 21);
     }
 
+    #[test]
+    fn multiline_error_source() {
+        let content = "var ok=true\n\
+            @!var msg: str=\"apple, bananas\\\n\
+            , fig, grape\\\n\
+            , lemon\"\n\
+            var list2: [int]=[1, 2]";
+
+        let results = YesDocParser::from_string(content, None);
+        assert_eq!(results.len(), 3);
+
+        match &results[1] {
+            ParseResult::Err {
+                line_number,
+                code,
+                source,
+                first_line_number,
+                ..
+            } => {
+                assert_eq!(*first_line_number, 2);
+                assert_eq!(*line_number, 4);
+                assert!(*code == ErrorCodes::BadTokenPosBang);
+                assert_eq!(
+                    source,
+                    "@!var msg: str=\"apple, bananas, fig, grape, lemon\""
+                );
+            }
+            _ => panic!("Error expected!"),
+        }
+    }
+
     #[test]
     fn space_delimiter_test() {
         let content = "x a=b -c";
@@ -363,11 +872,8 @@ mod tests {
         let first = results.first();
         assert_eq!(first.is_some(), true);
 
-        let element = match &first.unwrap() {
-            ParseResult::Ok {
-                line_number: _,
-                data: Elements::Standard { attrs: _, element },
-            } => element,
+        let element = match first.unwrap().data() {
+            Some(Elements::Standard { element, .. }) => element,
             _ => panic!("Standard element expected!"),
         };
 
@@ -395,11 +901,8 @@ mod tests {
         let first = results.first();
         assert_eq!(first.is_some(), true);
 
-        let element = match &first.unwrap() {
-            ParseResult::Ok {
-                line_number: _,
-                data: Elements::Standard { attrs: _, element },
-            } => element,
+        let element = match first.unwrap().data() {
+            Some(Elements::Standard { element, .. }) => element,
             _ => panic!("Standard element expected!"),
         };
 
@@ -425,6 +928,159 @@ mod tests {
         assert_eq!(arg4.val, "+f");
     }
 
+    #[test]
+    fn sticky_attribute_test() {
+        let content = "@@experimental\n\
+            @once\n\
+            a\n\
+            b\n\
+            c\n\
+            @@\n\
+            d";
+
+        let results = YesDocParser::from_string(content, None);
+        assert_eq!(results.len(), 4);
+
+        let attrs: Vec<Vec<&str>> = results
+            .iter()
+            .map(|result| match result.data() {
+                Some(Elements::Standard { attrs, .. }) => {
+                    attrs.iter().map(|a| a.text.as_str()).collect()
+                }
+                _ => panic!("Standard element expected!"),
+            })
+            .collect();
+
+        assert_eq!(attrs[0], vec!["experimental", "once"]);
+        assert_eq!(attrs[1], vec!["experimental"]);
+        assert_eq!(attrs[2], vec!["experimental"]);
+        assert!(attrs[3].is_empty());
+    }
+
+    #[test]
+    fn equal_edge_cases_test() {
+        let content = "x a==b\nx a=\nx ==";
+        let results = YesDocParser::from_string(content, None);
+        assert_eq!(results.len(), 3);
+
+        let args: Vec<Vec<(Option<&str>, &str)>> = results
+            .iter()
+            .map(|result| match result.data() {
+                Some(Elements::Standard { element, .. }) => element
+                    .args
+                    .iter()
+                    .map(|kv| (kv.key.as_deref(), kv.val.as_str()))
+                    .collect(),
+                _ => panic!("Standard element expected!"),
+            })
+            .collect();
+
+        // The value retains the second equal glyph.
+        assert_eq!(args[0], vec![(Some("a"), "=b")]);
+
+        // The value is empty.
+        assert_eq!(args[1], vec![(Some("a"), "")]);
+
+        // The token has no key and no value and is skipped.
+        assert!(args[2].is_empty());
+    }
+
+    #[test]
+    fn strict_equal_edge_cases_test() {
+        let content = "x a==b\nx a=\nx ==";
+        let options = ParserOptions {
+            strict: true,
+            ..Default::default()
+        };
+        let results = YesDocParser::from_string_with_options(content, None, options);
+        assert_eq!(results.len(), 3);
+
+        match &results[0] {
+            ParseResult::Err {
+                line_number, code, ..
+            } => {
+                assert_eq!(*line_number, 1);
+                assert!(*code == ErrorCodes::BadTokenPosEqual);
+            }
+            _ => panic!("Error expected!"),
+        }
+
+        match results[1].data() {
+            Some(Elements::Standard { element, .. }) => {
+                assert_eq!(element.get_key_value::<String>("a").unwrap(), "")
+            }
+            _ => panic!("Standard element expected!"),
+        }
+
+        match &results[2] {
+            ParseResult::Err {
+                line_number, code, ..
+            } => {
+                assert_eq!(*line_number, 3);
+                assert!(*code == ErrorCodes::BadTokenPosEqual);
+            }
+            _ => panic!("Error expected!"),
+        }
+    }
+
+    #[test]
+    fn raw_element_test() {
+        let content = "exec rm -rf /tmp\n\
+            exec rm -rf \"dir with spaces\", a=b\n\
+            other rm -rf /tmp";
+        let options = ParserOptions {
+            raw_elements: vec!["exec".to_owned()],
+            ..Default::default()
+        };
+        let results = YesDocParser::from_string_with_options(content, None, options);
+        assert_eq!(results.len(), 3);
+
+        let args: Vec<Vec<(Option<&str>, &str)>> = results
+            .iter()
+            .map(|result| match result.data() {
+                Some(Elements::Standard { element, .. }) => element
+                    .args
+                    .iter()
+                    .map(|kv| (kv.key.as_deref(), kv.val.as_str()))
+                    .collect(),
+                _ => panic!("Standard element expected!"),
+            })
+            .collect();
+
+        assert_eq!(args[0], vec![(None, "rm -rf /tmp")]);
+        assert_eq!(args[1], vec![(None, "rm -rf \"dir with spaces\", a=b")]);
+        assert_eq!(args[2], vec![(None, "rm"), (None, "-rf"), (None, "/tmp")]);
+    }
+
+    #[test]
+    fn mixed_arg_order_test() {
+        let content = "x a=b c d\nx a b=c\nx a=b c=d e\nx a=b c a=d e";
+        let results = YesDocParser::from_string(content, None);
+        assert_eq!(results.len(), 4);
+
+        let args: Vec<Vec<(Option<&str>, &str)>> = results
+            .iter()
+            .map(|result| match result.data() {
+                Some(Elements::Standard { element, .. }) => element
+                    .args
+                    .iter()
+                    .map(|kv| (kv.key.as_deref(), kv.val.as_str()))
+                    .collect(),
+                _ => panic!("Standard element expected!"),
+            })
+            .collect();
+
+        assert_eq!(args[0], vec![(Some("a"), "b"), (None, "c"), (None, "d")]);
+        assert_eq!(args[1], vec![(None, "a"), (Some("b"), "c")]);
+        assert_eq!(
+            args[2],
+            vec![(Some("a"), "b"), (Some("c"), "d"), (None, "e")]
+        );
+
+        // A repeated key updates the value but keeps its first position.
+        assert_eq!(args[3], vec![(Some("a"), "d"), (None, "c"), (None, "e")]);
+    }
+
     #[test]
     fn comma_delimiter_test() {
         let content = "frame duration = 1.0s , width = 10, height=20";
@@ -433,7 +1089,9 @@ mod tests {
 
         for result in &results {
             match result {
-                ParseResult::Ok { line_number, data } => println!("#{}: {}", line_number, data),
+                ParseResult::Ok {
+                    line_number, data, ..
+                } => println!("#{}: {}", line_number, data),
                 ParseResult::Err {
                     line_number,
                     message,
@@ -447,6 +1105,7 @@ mod tests {
                 ParseResult::Ok {
                     line_number: _,
                     data: Elements::Standard { attrs: _, element },
+                    depth: _,
                 } => element,
                 _ => panic!("Standard element expected!"),
             }
@@ -467,4 +1126,860 @@ mod tests {
         assert_eq!(width, 10);
         assert_eq!(height, 20);
     }
+
+    #[test]
+    fn parse_globals_first_test() {
+        let doc = "!version 2
+            span <hello world> x";
+
+        let (globals, parse) = YesDocParser::parse_globals_first(doc, None);
+
+        assert_eq!(globals.len(), 1);
+        assert_eq!(globals[0].text, "version");
+
+        // Angle bracket literals are only enabled for version 2 and above.
+        let version: u32 = globals[0].args[0].val.parse().unwrap();
+        let literals = match version {
+            v if v >= 2 => Some(vec![Literal::new(b'<', b'>').unwrap()]),
+            _ => None,
+        };
+
+        let results = parse(literals, ParserOptions::default());
+        assert_eq!(results.len(), 2);
+
+        match results[1].data() {
+            Some(Elements::Standard { element, .. }) => {
+                assert_eq!(element.text, "span");
+                assert_eq!(element.args.len(), 2);
+                assert_eq!(element.args[0].val, "<hello world>");
+                assert_eq!(element.args[1].val, "x");
+            }
+            _ => panic!("Standard element expected!"),
+        }
+    }
+
+    #[test]
+    fn trim_literals_test() {
+        let doc = "data name=\"  x  \" list=[ 1 , 2 ]";
+        let literals = Some(vec![Literal::new(b'[', b']').unwrap()]);
+
+        for (trim_literals, name, list) in [(false, "  x  ", "[ 1 , 2 ]"), (true, "x", "[1 , 2]")] {
+            let options = ParserOptions {
+                trim_literals,
+                ..Default::default()
+            };
+
+            let results = YesDocParser::from_string_with_options(doc, literals.clone(), options);
+            assert_eq!(results.len(), 1);
+
+            match results[0].data() {
+                Some(Elements::Standard { element, .. }) => {
+                    assert_eq!(element.get_key_value::<String>("name").unwrap(), name);
+                    assert_eq!(element.get_key_value::<String>("list").unwrap(), list);
+                }
+                _ => panic!("Standard element expected!"),
+            }
+        }
+    }
+
+    #[test]
+    fn indent_sensitive_test() {
+        let doc = "menu\n  item open\n    shortcut ctrl o\n\titem close\n\t  shortcut ctrl w";
+
+        let depths = |options: ParserOptions| -> Vec<Option<usize>> {
+            YesDocParser::from_string_with_options(doc, None, options)
+                .iter()
+                .map(|result| match result {
+                    ParseResult::Ok { depth, .. } => *depth,
+                    _ => panic!("Element expected!"),
+                })
+                .collect()
+        };
+
+        // Indentation is ignored by default.
+        assert_eq!(depths(ParserOptions::default()), vec![None; 5]);
+
+        let options = ParserOptions {
+            indent_sensitive: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            depths(options),
+            vec![Some(0), Some(2), Some(4), Some(4), Some(6)]
+        );
+
+        let options = ParserOptions {
+            indent_sensitive: true,
+            tab_width: 2,
+            ..Default::default()
+        };
+        assert_eq!(
+            depths(options),
+            vec![Some(0), Some(2), Some(4), Some(2), Some(4)]
+        );
+    }
+
+    #[test]
+    fn doc_comments_test() {
+        let content = "# The main window.\n\
+            # Sizes are in pixels.\n\
+            @resizable\n\
+            window width=320 height=240\n\
+            # Detached.\n\
+            \n\
+            volume sfx=100";
+
+        let options = ParserOptions {
+            doc_comments: true,
+            ..Default::default()
+        };
+        let results = YesDocParser::from_string_with_options(content, None, options);
+        assert_eq!(results.len(), 4);
+
+        match results[0].data() {
+            Some(Elements::Standard { element, attrs }) => {
+                assert_eq!(element.text, "window");
+                assert_eq!(
+                    element.doc,
+                    vec![" The main window.", " Sizes are in pixels."]
+                );
+                assert_eq!(attrs.len(), 1);
+            }
+            _ => panic!("Standard element expected!"),
+        }
+
+        // The blank line breaks the association.
+        assert_eq!(
+            results[1].data().unwrap().comment_text(),
+            Some(" Detached.")
+        );
+        assert!(results[2].is_err());
+
+        match results[3].data() {
+            Some(Elements::Standard { element, .. }) => assert!(element.doc.is_empty()),
+            _ => panic!("Standard element expected!"),
+        }
+
+        // Comments are standalone by default.
+        let results = YesDocParser::from_string(content, None);
+        assert_eq!(results.len(), 6);
+    }
+
+    #[test]
+    fn error_codes_test() {
+        let literals = Some(vec![Literal::new(b'[', b']').unwrap()]);
+        let options = ParserOptions {
+            strict: true,
+            max_line_length: Some(16),
+            ..Default::default()
+        };
+
+        let content = "x a=1 a=2\n\
+            x list=[1 2\n\
+            x msg=\"hello\n\
+            x a=1 b=2 c=3 d=4";
+        let results = YesDocParser::from_string_with_options(content, literals.clone(), options);
+
+        let codes: Vec<Option<&ErrorCodes>> = results
+            .iter()
+            .map(|result| match result {
+                ParseResult::Err { code, .. } => Some(code),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(
+            codes,
+            vec![
+                Some(&ErrorCodes::DuplicateKey),
+                Some(&ErrorCodes::UnbalancedLiteral),
+                Some(&ErrorCodes::UnterminatedQuote),
+                Some(&ErrorCodes::LineTooLong),
+            ]
+        );
+
+        // Only the unterminated literals are errors without the options.
+        let results = YesDocParser::from_string(content, literals);
+        let errors: Vec<usize> = results
+            .iter()
+            .filter(|result| result.is_err())
+            .map(|result| result.line_number())
+            .collect();
+        assert_eq!(errors, vec![2, 3]);
+    }
+
+    #[test]
+    fn reserved_glyphs_test() {
+        let options = ParserOptions {
+            reserved: GlyphSet::RESERVED.without(b'#'),
+            ..ParserOptions::default()
+        };
+
+        let content = "#tag color=red\n!global";
+        let results = YesDocParser::from_string_with_options(content, None, options);
+
+        assert_eq!(results.len(), 2);
+
+        match results[0].data() {
+            Some(Elements::Global(element)) => assert_eq!(element.text, "global"),
+            _ => panic!("Global element expected!"),
+        }
+
+        match results[1].data() {
+            Some(Elements::Standard { element, .. }) => {
+                assert_eq!(element.text, "#tag");
+                assert_eq!(element.args.len(), 1);
+            }
+            _ => panic!("Standard element expected!"),
+        }
+
+        assert!(Glyphs::is_reserved(b'#'));
+        assert!(!GlyphSet::RESERVED.without(b'#').contains(b'#'));
+        assert!(GlyphSet::empty().with(b'~').contains(b'~'));
+    }
+
+    #[test]
+    fn rest_capture_test() {
+        let options = ParserOptions {
+            raw_elements: vec!["title".to_owned()],
+            ..Default::default()
+        };
+        let results = YesDocParser::from_string_with_options(
+            "title My Great App, v2\n!title   My Great App, v2",
+            None,
+            options,
+        );
+        assert_eq!(results.len(), 2);
+
+        for result in &results {
+            let element = match result.data() {
+                Some(Elements::Standard { element, .. } | Elements::Global(element)) => element,
+                _ => panic!("Element expected!"),
+            };
+
+            assert_eq!(element.text, "title");
+            assert_eq!(element.args.len(), 1);
+            assert_eq!(element.args[0].key, None);
+            assert_eq!(element.args[0].val, "My Great App, v2");
+        }
+    }
+
+    #[test]
+    fn parse_with_diagnostics_test() {
+        let content = "!version 1.0\n!version 2.0\n@!bad\nwindow\n@unused";
+        let (results, diagnostics) =
+            YesDocParser::parse_with_diagnostics(content, None, ParserOptions::default());
+
+        // Only the malformed line is an error.
+        let errors: Vec<usize> = results
+            .iter()
+            .filter(|result| result.is_err())
+            .map(|result| result.line_number())
+            .collect();
+        assert_eq!(errors, vec![3]);
+
+        let warnings: Vec<(usize, Lint, Severity)> = diagnostics
+            .into_iter()
+            .map(|d| (d.line_number, d.lint, d.severity))
+            .collect();
+        assert_eq!(
+            warnings,
+            vec![
+                (2, Lint::DuplicateGlobal, Severity::Warning),
+                (5, Lint::DanglingAttribute, Severity::Warning),
+            ]
+        );
+    }
+
+    #[test]
+    fn append_glyph_test() {
+        let content = "x tags+=a tags+=b\nx tags=a, tags += b, c=1\nx tags=a tags=b";
+        let options = ParserOptions {
+            append_glyph: Some('+'),
+            strict: true,
+            ..Default::default()
+        };
+        let results = YesDocParser::from_string_with_options(content, None, options);
+        assert_eq!(results.len(), 3);
+
+        let tags: Vec<Option<Vec<String>>> = results
+            .iter()
+            .map(|result| match result.data() {
+                Some(Elements::Standard { element, .. }) => element.get_key_value_vec("tags"),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(tags[0], Some(vec!["a".to_owned(), "b".to_owned()]));
+        assert_eq!(tags[1], Some(vec!["a".to_owned(), "b".to_owned()]));
+
+        // Assigning with the equal glyph alone is still a duplicate key.
+        match &results[2] {
+            ParseResult::Err { code, .. } => assert!(*code == ErrorCodes::DuplicateKey),
+            _ => panic!("Error expected!"),
+        }
+
+        // Without the option the glyph is part of the key.
+        let results = YesDocParser::from_string("x tags+=a tags+=b", None);
+        match results[0].data() {
+            Some(Elements::Standard { element, .. }) => {
+                assert_eq!(
+                    element.get_key_value::<String>("tags+"),
+                    Some("b".to_owned())
+                );
+                assert_eq!(element.get_key_value_vec::<String>("tags"), Some(vec![]));
+            }
+            _ => panic!("Standard element expected!"),
+        }
+    }
+
+    #[test]
+    fn windows_paths_test() {
+        const PATHS: [&str; 4] = [
+            r"C:\a\b",
+            r"\\server\share",
+            r"C:\Program Files\App",
+            r"D:\games\",
+        ];
+
+        for path in PATHS {
+            // Paths with spaces must be quoted to be bare values.
+            let bare = if path.contains(' ') {
+                format!("\"{}\"", path)
+            } else {
+                path.to_owned()
+            };
+
+            // A trailing backslash would continue the line, so it is followed
+            // by another argument.
+            let content = format!("path {} dir={} x\nquoted \"{}\" x", bare, bare, path);
+            let results = YesDocParser::from_string(&content, None);
+            assert_eq!(results.len(), 2);
+
+            for result in &results {
+                match result.data() {
+                    Some(Elements::Standard { element, .. }) => {
+                        assert_eq!(element.args[0].val, path);
+                        if element.text == "path" {
+                            assert_eq!(element.get_key_value::<String>("dir").unwrap(), path);
+                        }
+                    }
+                    _ => panic!("Standard element expected!"),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn from_bytes_lossy_test() {
+        let body = b"!version 1.0\nname first=caf\xe9 last=ok\n\xff\xfe";
+        let results = YesDocParser::from_bytes_lossy(body, None);
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(|result| !result.is_err()));
+
+        match results[1].data() {
+            Some(Elements::Standard { element, .. }) => {
+                assert_eq!(
+                    element.get_key_value::<String>("first").unwrap(),
+                    "caf\u{FFFD}"
+                );
+                assert_eq!(element.get_key_value::<String>("last").unwrap(), "ok");
+            }
+            _ => panic!("Standard element expected!"),
+        }
+
+        match results[2].data() {
+            Some(Elements::Standard { element, .. }) => {
+                assert_eq!(element.text, "\u{FFFD}\u{FFFD}")
+            }
+            _ => panic!("Standard element expected!"),
+        }
+    }
+
+    #[test]
+    fn quoted_newlines_test() {
+        let content = "text body=\"Roses are red,\\\n\
+            Violets are blue.\\\n\
+            \" author=anon \\\n\
+            year=1784";
+        let options = ParserOptions {
+            quoted_newlines: true,
+            ..Default::default()
+        };
+
+        let results = YesDocParser::from_string_with_options(content, None, options);
+        assert_eq!(results.len(), 1);
+
+        match results[0].data() {
+            Some(Elements::Standard { element, .. }) => {
+                assert_eq!(
+                    element.get_key_value::<String>("body").unwrap(),
+                    "Roses are red,\nViolets are blue.\n"
+                );
+                assert_eq!(element.get_key_value::<String>("author").unwrap(), "anon");
+                assert_eq!(element.get_key_value::<u16>("year"), Some(1784));
+            }
+            _ => panic!("Standard element expected!"),
+        }
+
+        // Without the option the fragments are joined with nothing.
+        let results = YesDocParser::from_string(content, None);
+        match results[0].data() {
+            Some(Elements::Standard { element, .. }) => assert_eq!(
+                element.get_key_value::<String>("body").unwrap(),
+                "Roses are red,Violets are blue."
+            ),
+            _ => panic!("Standard element expected!"),
+        }
+
+        // An escaped quote or a quote inside of a custom literal is not open.
+        let options = ParserOptions {
+            quoted_newlines: true,
+            quote_escapes: true,
+            ..Default::default()
+        };
+        let literals = Some(vec![Literal::new(b'[', b']').unwrap()]);
+
+        for content in ["text a=\"\\\"\" \\\nnext=1", "text list=[a\"b] \\\nnext=1"] {
+            let results =
+                YesDocParser::from_string_with_options(content, literals.clone(), options.clone());
+
+            match results[0].data() {
+                Some(Elements::Standard { element, .. }) => {
+                    assert_eq!(element.get_key_value::<u8>("next"), Some(1), "{}", content)
+                }
+                _ => panic!("Standard element expected!"),
+            }
+        }
+    }
+
+    #[test]
+    fn multiline_error_recovery_test() {
+        let content = "@!bad a=1\\\n\
+            b=2\n\
+            window width=320\n\
+            @!worse\\\n\
+            \\\n\
+            c=3\n\
+            volume sfx=100\\\n\
+            , music=50";
+
+        let results = YesDocParser::from_string(content, None);
+        assert_eq!(results.len(), 4);
+
+        match &results[0] {
+            ParseResult::Err {
+                line_number,
+                first_line_number,
+                code,
+                ..
+            } => {
+                assert_eq!((*first_line_number, *line_number), (1, 2));
+                assert!(*code == ErrorCodes::BadTokenPosBang);
+            }
+            _ => panic!("Error expected!"),
+        }
+
+        match results[1].data() {
+            Some(Elements::Standard { element, .. }) => {
+                assert_eq!(element.text, "window");
+                assert_eq!(element.args.len(), 1);
+                assert_eq!(element.get_key_value::<u16>("width"), Some(320));
+            }
+            _ => panic!("Standard element expected!"),
+        }
+
+        match &results[2] {
+            ParseResult::Err {
+                line_number,
+                first_line_number,
+                ..
+            } => assert_eq!((*first_line_number, *line_number), (4, 6)),
+            _ => panic!("Error expected!"),
+        }
+
+        match results[3].data() {
+            Some(Elements::Standard { element, .. }) => {
+                assert_eq!(element.text, "volume");
+                assert_eq!(element.get_key_value::<u8>("music"), Some(50));
+            }
+            _ => panic!("Standard element expected!"),
+        }
+        assert_eq!(results[3].line_number(), 8);
+    }
+
+    #[test]
+    fn blank_lines_test() {
+        let content = "a\n\n   \nb\n\t";
+
+        let options = ParserOptions {
+            blank_lines: true,
+            ..Default::default()
+        };
+        let results = YesDocParser::from_string_with_options(content, None, options);
+
+        let kinds: Vec<(usize, &str)> = results
+            .iter()
+            .map(|result| {
+                let kind = match result.data() {
+                    Some(Elements::Blank) => "blank",
+                    Some(Elements::Standard { .. }) => "standard",
+                    _ => panic!("Standard or blank element expected!"),
+                };
+                (result.line_number(), kind)
+            })
+            .collect();
+
+        assert_eq!(
+            kinds,
+            vec![
+                (1, "standard"),
+                (2, "blank"),
+                (3, "blank"),
+                (4, "standard"),
+                (5, "blank")
+            ]
+        );
+
+        // By default blank lines are errors.
+        let results = YesDocParser::from_string(content, None);
+        match &results[1] {
+            ParseResult::Err { code, .. } => assert!(*code == ErrorCodes::EolNoData),
+            _ => panic!("Error expected!"),
+        }
+    }
+
+    #[test]
+    fn max_args_per_line_test() {
+        let many = vec!["a"; 1000].join(", ");
+        let content = format!("x 1 2\ny {}\n!z 1 2 3 4\nw \"a, b\" c=\"d e\" f", many);
+        let options = ParserOptions {
+            max_args_per_line: Some(3),
+            ..Default::default()
+        };
+        let (results, diagnostics) = YesDocParser::parse_with_diagnostics(&content, None, options);
+
+        let args: Vec<(usize, Vec<&str>)> = results
+            .iter()
+            .map(|result| match result.data() {
+                Some(Elements::Standard { element, .. }) | Some(Elements::Global(element)) => (
+                    result.line_number(),
+                    element.args.iter().map(|kv| kv.val.as_str()).collect(),
+                ),
+                _ => panic!("Element expected!"),
+            })
+            .collect();
+        assert_eq!(
+            args,
+            vec![
+                (3, vec!["1", "2", "3"]),
+                (1, vec!["1", "2"]),
+                (2, vec!["a", "a", "a"]),
+                (4, vec!["a, b", "d e", "f"]),
+            ]
+        );
+
+        let warnings: Vec<(usize, Lint)> = diagnostics
+            .into_iter()
+            .map(|d| (d.line_number, d.lint))
+            .collect();
+        assert_eq!(
+            warnings,
+            vec![(2, Lint::TruncatedArgs), (3, Lint::TruncatedArgs)]
+        );
+    }
+
+    #[test]
+    fn elements_from_str_test() -> Result<(), Box<dyn std::error::Error>> {
+        let el: Elements = "window width=320, height=240".parse()?;
+        match el {
+            Elements::Standard { element, .. } => {
+                assert_eq!(element.text, "window");
+                assert_eq!(element.get_key_value::<u16>("height"), Some(240));
+            }
+            _ => panic!("Standard element expected!"),
+        }
+
+        assert!(matches!("!version 1.0".parse()?, Elements::Global(_)));
+        assert!(matches!("@hidden".parse()?, Elements::Attribute(_)));
+
+        let err = "window\nlabel".parse::<Elements>().err().unwrap();
+        assert!(err.code == ErrorCodes::Runtime);
+        assert_eq!(err.to_string(), "#1: Expected exactly one line.");
+
+        let err = "@!bad".parse::<Elements>().err().unwrap();
+        assert!(err.code == ErrorCodes::BadTokenPosBang);
+        assert!("".parse::<Elements>().err().unwrap().code == ErrorCodes::EolNoData);
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_error_test() {
+        use std::io::{self, BufRead, BufReader, Read};
+
+        /// Reads one line and then fails like a disk would.
+        struct FailingReader(usize);
+
+        impl Read for FailingReader {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                self.0 += 1;
+                match self.0 {
+                    1 => {
+                        let line = b"window width=320\n";
+                        buf[..line.len()].copy_from_slice(line);
+                        Ok(line.len())
+                    }
+                    _ => Err(io::Error::other("disk read failure")),
+                }
+            }
+        }
+
+        let lines = BufReader::new(FailingReader(0)).lines();
+        let result = YesDocParser::read_lines(lines, None, ParserOptions::default());
+        assert_eq!(result.err().unwrap().to_string(), "disk read failure");
+
+        // Invalid UTF-8 is an error as well.
+        let lines = (&b"a\n\xFF\nb"[..]).lines();
+        let result = YesDocParser::read_lines(lines, None, ParserOptions::default());
+        assert!(result.err().unwrap().kind() == io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn continuation_keeps_interior_backslashes() {
+        let content = "copy from=C:\\temp\\file \\\n    to=D:\\backup\\ \\\n    note=\"a\\b\"";
+        let results = YesDocParser::from_string(content, None);
+        assert_eq!(results.len(), 1);
+
+        match results[0].data() {
+            Some(Elements::Standard { element, .. }) => {
+                let vals: Vec<&str> = element.args.iter().map(|kv| kv.val.as_str()).collect();
+                assert_eq!(vals, vec!["C:\\temp\\file", "D:\\backup\\", "a\\b"]);
+            }
+            _ => panic!("Standard element expected!"),
+        }
+    }
+
+    #[test]
+    fn unterminated_spans_test() {
+        let literals = Some(vec![Literal::new(b'[', b']').unwrap()]);
+
+        let content = "x name=\"unterminated, b=1\n\
+            x name=\"ok\" list=[1 2]\n\
+            x list=[1 2, b=1\n\
+            x \"a\\\n\
+            b\"";
+        let results = YesDocParser::from_string(content, literals.clone());
+
+        let errors: Vec<(usize, &ErrorCodes)> = results
+            .iter()
+            .filter_map(|result| match result {
+                ParseResult::Err {
+                    line_number, code, ..
+                } => Some((*line_number, code)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                (1, &ErrorCodes::UnterminatedQuote),
+                (3, &ErrorCodes::UnbalancedLiteral),
+            ]
+        );
+
+        let results = YesDocParser::from_string("x name=\"unterminated", None);
+        assert!(matches!(
+            results.first(),
+            Some(ParseResult::Err {
+                line_number: 1,
+                code: ErrorCodes::UnterminatedQuote,
+                ..
+            })
+        ));
+
+        let results = YesDocParser::from_string("x a=[b c", literals.clone());
+        assert!(matches!(
+            results.first(),
+            Some(ParseResult::Err {
+                line_number: 1,
+                code: ErrorCodes::UnbalancedLiteral,
+                ..
+            })
+        ));
+
+        // Strict mode reports the same errors.
+        let options = ParserOptions {
+            strict: true,
+            ..Default::default()
+        };
+        let results = YesDocParser::from_string_with_options(content, literals.clone(), options);
+        assert_eq!(results.iter().filter(|result| result.is_err()).count(), 2);
+
+        // With lenient literals the span swallows the rest of the line.
+        let options = ParserOptions {
+            lenient_literals: true,
+            ..Default::default()
+        };
+        let results = YesDocParser::from_string_with_options(content, literals.clone(), options);
+        assert!(results.iter().all(|result| !result.is_err()));
+        match results[0].data() {
+            Some(Elements::Standard { element, .. }) => {
+                assert_eq!(element.args.len(), 1);
+                assert_eq!(element.args[0].val, "\"unterminated, b=1");
+            }
+            _ => panic!("Standard element expected!"),
+        }
+    }
+
+    #[test]
+    fn to_yes_string_test() {
+        let content = "!version 1.0\n\
+            # Window settings\n\
+            @resizable\n\
+            @@theme dark\n\
+            window title=\"My App, v2\" size=\"a=b\" \"full name\"=x\n\
+            label \"#tag\", \" padded \", plain\n\
+            @@\n\
+            !lang en";
+        let results = YesDocParser::from_string(content, None);
+        let source = YesDocParser::to_yes_string(&results);
+        let reparsed = YesDocParser::from_string(&source, None);
+
+        // The source ends with a newline which reads as one more empty line.
+        let describe = |results: &[ParseResult]| -> Vec<String> {
+            results
+                .iter()
+                .filter_map(|result| match result.data() {
+                    Some(Elements::Standard { attrs, element }) => {
+                        let attrs: Vec<String> = attrs.iter().map(|a| a.to_string()).collect();
+                        Some(format!("{} {:?}", element, attrs))
+                    }
+                    Some(data) => Some(data.to_string()),
+                    None => None,
+                })
+                .collect()
+        };
+
+        assert!(results.iter().all(|result| !result.is_err()));
+        assert_eq!(describe(&results), describe(&reparsed));
+        assert_eq!(YesDocParser::to_yes_string(&reparsed), source);
+        assert!(source.starts_with("!version 1.0\n!lang en\n# Window settings\n"));
+    }
+
+    #[test]
+    fn error_placement_test() {
+        let content = "@!bad\na\n!g1\n@!bad\nb\n!g2";
+        let lines = |error_placement: ErrorPlacement| -> Vec<(usize, bool)> {
+            let options = ParserOptions {
+                error_placement,
+                ..Default::default()
+            };
+
+            YesDocParser::from_string_with_options(content, None, options)
+                .iter()
+                .map(|result| (result.line_number(), result.is_err()))
+                .collect()
+        };
+
+        assert_eq!(
+            lines(ErrorPlacement::Interleaved),
+            vec![
+                (3, false),
+                (6, false),
+                (1, true),
+                (2, false),
+                (4, true),
+                (5, false)
+            ]
+        );
+        assert_eq!(
+            lines(ErrorPlacement::Last),
+            vec![
+                (3, false),
+                (6, false),
+                (2, false),
+                (5, false),
+                (1, true),
+                (4, true)
+            ]
+        );
+        assert_eq!(
+            lines(ErrorPlacement::OriginalOrder),
+            vec![
+                (1, true),
+                (3, false),
+                (6, false),
+                (4, true),
+                (2, false),
+                (5, false)
+            ]
+        );
+    }
+
+    #[test]
+    fn iter_reader_test() {
+        // Endless blank lines after the elements. Only a lazy reader returns.
+        let head = io::Cursor::new("# Doc\nwindow w=320 \\\n  h=240\n!version 2\n");
+        let reader = io::BufReader::new(head.chain(io::repeat(b'\n')));
+
+        let options = ParserOptions {
+            doc_comments: true,
+            ..Default::default()
+        };
+        let results: Vec<ParseResult> =
+            YesDocParser::iter_reader_with_options(reader, None, options)
+                .take(2)
+                .collect();
+
+        assert_eq!(results.len(), 2);
+
+        match results[0].data() {
+            Some(Elements::Standard { element, .. }) => {
+                assert_eq!(element.text, "window");
+                assert_eq!(element.doc, vec![" Doc"]);
+                assert_eq!(element.get_key_value::<u32>("h"), Some(240));
+            }
+            _ => panic!("Standard element expected!"),
+        }
+
+        // Globals are yielded in order instead of hoisted.
+        match results[1].data() {
+            Some(Elements::Global(element)) => assert_eq!(element.text, "version"),
+            _ => panic!("Global element expected!"),
+        }
+        assert_eq!(results[1].line_number(), 4);
+    }
+
+    #[test]
+    fn error_column_test() {
+        let options = ParserOptions {
+            strict: true,
+            ..Default::default()
+        };
+        let content = "window w=1\n@!foo x=1\nlabel text=\"abc \\\n  def";
+        let results = YesDocParser::from_string_with_options(content, None, options);
+
+        let errors: Vec<(usize, ErrorCodes, usize)> = results
+            .into_iter()
+            .filter_map(|result| match result {
+                ParseResult::Err {
+                    line_number,
+                    code,
+                    column,
+                    ..
+                } => Some((line_number, code, column)),
+                _ => None,
+            })
+            .collect();
+
+        // Columns of multiline elements index into the joined line.
+        assert!(matches!(
+            errors.as_slice(),
+            [
+                (2, ErrorCodes::BadTokenPosBang, 1),
+                (4, ErrorCodes::UnterminatedQuote, 11)
+            ]
+        ));
+    }
 }
@@ -0,0 +1,88 @@
+/// [Lint] identifies which check produced a [Diagnostic].
+#[derive(Debug, PartialEq)]
+pub enum Lint {
+    /// A global was declared more than once. Since globals are hoisted, a
+    /// consumer which expects one value may silently pick either.
+    DuplicateGlobal,
+
+    /// An attribute was declared but no standard element followed it, so it
+    /// was never applied.
+    DanglingAttribute,
+
+    /// A standard or global element has an empty name.
+    EmptyElement,
+
+    /// A line had more arguments than [crate::options::ParserOptions::max_args_per_line]
+    /// and the rest were dropped.
+    TruncatedArgs,
+
+    /// A misplaced element prefix was read as part of the name by
+    /// [crate::options::ParserOptions::recover_prefixes]. Since recovery is
+    /// opted into, this is reported as [Severity::Info].
+    RecoveredPrefix,
+}
+
+impl Lint {
+    /// Returns the [Severity] a [Diagnostic] of this kind is reported with.
+    pub fn severity(&self) -> Severity {
+        match self {
+            Lint::DuplicateGlobal => Severity::Warning,
+            Lint::DanglingAttribute => Severity::Warning,
+            Lint::EmptyElement => Severity::Warning,
+            Lint::TruncatedArgs => Severity::Warning,
+            Lint::RecoveredPrefix => Severity::Info,
+        }
+    }
+}
+
+/// [Severity] ranks how actionable a [Diagnostic] is. Neither level is fatal.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Severity {
+    /// The document may be intentional but is noteworthy.
+    Info,
+
+    /// The document is likely not what the author intended.
+    Warning,
+}
+
+/// A non-fatal finding reported by [crate::document::Document::lint].
+/// Unlike [crate::ParseResult::Err], the document was parsed successfully.
+#[derive(Debug, PartialEq)]
+pub struct Diagnostic {
+    pub line_number: usize,
+    pub severity: Severity,
+    pub lint: Lint,
+    pub message: String,
+}
+
+/// [LintOptions] toggle each check of [crate::document::Document::lint_with].
+/// Every check is enabled by default.
+#[derive(Clone)]
+pub struct LintOptions {
+    /// Report [Lint::DuplicateGlobal].
+    pub duplicate_globals: bool,
+
+    /// Report [Lint::DanglingAttribute].
+    pub dangling_attributes: bool,
+
+    /// Report [Lint::EmptyElement].
+    pub empty_elements: bool,
+
+    /// Report [Lint::TruncatedArgs].
+    pub truncated_args: bool,
+
+    /// Report [Lint::RecoveredPrefix].
+    pub recovered_prefixes: bool,
+}
+
+impl Default for LintOptions {
+    fn default() -> Self {
+        LintOptions {
+            duplicate_globals: true,
+            dangling_attributes: true,
+            empty_elements: true,
+            truncated_args: true,
+            recovered_prefixes: true,
+        }
+    }
+}
@@ -1,9 +1,59 @@
 use crate::enums::Glyphs;
 
-#[derive(Eq, Hash, Clone)]
+/// A pair of [Literal::begin] and [Literal::end] bytes denoting a span.
+///
+/// Literals are ordered by [Literal::begin] and then by [Literal::end].
+/// The parser tests literals in this order, so when two literals share the
+/// same [Literal::begin] byte, the one with the smallest [Literal::end]
+/// byte is always chosen.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
 pub struct Literal {
     pub begin: u8,
     pub end: u8,
+
+    /// When [Some], this byte escapes the byte after it inside of the span so
+    /// that `[a\]b]` is one span. The escape byte is removed from a value
+    /// which is one span when it precedes [Literal::end] or itself, so the
+    /// value is `[a]b]`. Defaults to [None] and nothing is escaped.
+    pub escape: Option<u8>,
+
+    /// When [Some], a value which is exactly one span of this literal is
+    /// tagged with this name. See [crate::keyval::KeyVal::literal_name].
+    /// Defaults to [None].
+    pub name: Option<String>,
+
+    /// When true, the [Literal::begin] and [Literal::end] bytes are removed
+    /// from a value which is exactly one span, so `"abc"` has the value
+    /// `abc`. When false they are kept, so a code literal `` `a b` `` keeps
+    /// its backticks. Defaults to true for [Literal::build_quotes] and false
+    /// for [Literal::new], which is how the parser has always treated them.
+    pub strip_delimiters: bool,
+}
+
+impl Default for Literal {
+    /// Returns a [Literal] whose bytes are zero and which has no
+    /// [Literal::escape] or [Literal::name], like [Literal::new], so that only
+    /// the fields which differ need to be set:
+    ///
+    /// ```
+    /// use yes_parser::literal::Literal;
+    ///
+    /// let literal = Literal {
+    ///     begin: b'[',
+    ///     end: b']',
+    ///     ..Default::default()
+    /// };
+    /// assert!(literal == Literal::new(b'[', b']').unwrap());
+    /// ```
+    fn default() -> Self {
+        Literal {
+            begin: 0,
+            end: 0,
+            escape: None,
+            name: None,
+            strip_delimiters: false,
+        }
+    }
 }
 
 impl Literal {
@@ -19,7 +69,82 @@ impl Literal {
             return Err("Literal::end cannot contain a reserved character.");
         }
 
-        Ok(Literal { begin, end })
+        Ok(Literal {
+            begin,
+            end,
+            escape: None,
+            name: None,
+            strip_delimiters: false,
+        })
+    }
+
+    /// Returns [self] with [Literal::escape] set to [escape].
+    pub fn with_escape(mut self, escape: u8) -> Literal {
+        self.escape = Some(escape);
+        self
+    }
+
+    /// Returns [self] with [Literal::strip_delimiters] set to [strip].
+    pub fn with_strip_delimiters(mut self, strip: bool) -> Literal {
+        self.strip_delimiters = strip;
+        self
+    }
+
+    /// Returns [self] with [Literal::name] set to [name].
+    pub fn with_name(mut self, name: &str) -> Literal {
+        self.name = Some(name.to_owned());
+        self
+    }
+
+    /// Returns the offset from [start] of the first [Literal::end] byte in
+    /// [bytes] which is not escaped by [Literal::escape]. Otherwise [None].
+    pub(crate) fn find_end(&self, bytes: &[u8], start: usize) -> Option<usize> {
+        let mut i = start;
+
+        while i < bytes.len() {
+            if Some(bytes[i]) == self.escape {
+                i += 2;
+                continue;
+            }
+
+            if bytes[i] == self.end {
+                return Some(i - start);
+            }
+
+            i += 1;
+        }
+
+        None
+    }
+
+    /// Removes [Literal::escape] from [inner], the text between the begin and
+    /// end bytes of one span, where it precedes [Literal::end] or itself.
+    pub(crate) fn unescape(&self, inner: &str) -> String {
+        let escape = match self.escape {
+            Some(escape) => escape as char,
+            None => return inner.to_owned(),
+        };
+
+        let mut out = String::with_capacity(inner.len());
+        let mut chars = inner.chars();
+
+        while let Some(c) = chars.next() {
+            if c == escape {
+                match chars.next() {
+                    Some(next) if next == self.end as char || next == escape => out.push(next),
+                    Some(next) => {
+                        out.push(c);
+                        out.push(next);
+                    }
+                    None => out.push(c),
+                }
+                continue;
+            }
+
+            out.push(c);
+        }
+
+        out
     }
 
     /// Constructs a [Literal] set which represent quoted strings.
@@ -30,12 +155,133 @@ impl Literal {
         Literal {
             begin: Glyphs::Quote.value(),
             end: Glyphs::Quote.value(),
+            escape: None,
+            name: None,
+            strip_delimiters: true,
         }
     }
+
+    /// Returns an [Err] if two [Literal]s in [literals] share the same
+    /// [Literal::begin] byte but have a different [Literal::end] byte.
+    /// [Literal::build_quotes] is always passed into the parser, so it is
+    /// checked against [literals] as well.
+    ///
+    /// The parser resolves such a set deterministically (see [Literal]), but
+    /// it is likely a mistake. Call this before parsing to reject it instead.
+    pub fn validate(literals: &[Literal]) -> Result<(), &'static str> {
+        let quotes = Literal::build_quotes();
+        let all: Vec<&Literal> = literals.iter().chain([&quotes]).collect();
+
+        for (i, a) in all.iter().enumerate() {
+            if all[i + 1..]
+                .iter()
+                .any(|b| a.begin == b.begin && a.end != b.end)
+            {
+                return Err(
+                    "Literals cannot share the same Literal::begin with a different Literal::end.",
+                );
+            }
+        }
+
+        Ok(())
+    }
 }
 
-impl PartialEq for Literal {
-    fn eq(&self, other: &Self) -> bool {
-        self.begin == other.begin && self.end == other.end
+#[cfg(test)]
+mod tests {
+    use crate::{enums::Elements, literal::Literal, ParseResult, YesDocParser};
+
+    #[test]
+    fn literal_ordering() {
+        let a = Literal::new(b'<', b'>').unwrap();
+        let b = Literal::new(b'<', b'|').unwrap();
+        let c = Literal::new(b'[', b']').unwrap();
+
+        let mut list = vec![c.clone(), b.clone(), a.clone()];
+        list.sort();
+
+        assert!(list == vec![a, b, c]);
+    }
+
+    #[test]
+    fn validate_conflicting_literals() {
+        let angle = Literal::new(b'<', b'>').unwrap();
+        let square = Literal::new(b'[', b']').unwrap();
+        let conflict = Literal::new(b'<', b']').unwrap();
+        let quote = Literal {
+            begin: b'"',
+            end: b'|',
+            ..Default::default()
+        };
+
+        assert!(Literal::validate(&[angle.clone(), square.clone()]).is_ok());
+        assert!(Literal::validate(&[angle.clone(), angle.clone()]).is_ok());
+        assert!(Literal::validate(&[angle, square, conflict]).is_err());
+
+        // The quotes are always a literal, so this conflicts too.
+        assert!(Literal::validate(&[quote]).is_err());
+    }
+
+    #[test]
+    fn shared_begin_tie_break() {
+        let content = "x <a|b c> d";
+
+        // Regardless of registration order, `<` and `>` must always win.
+        for literals in [
+            vec![
+                Literal::new(b'<', b'|').unwrap(),
+                Literal::new(b'<', b'>').unwrap(),
+            ],
+            vec![
+                Literal::new(b'<', b'>').unwrap(),
+                Literal::new(b'<', b'|').unwrap(),
+            ],
+        ] {
+            let results = YesDocParser::from_string(content, Some(literals));
+            let element = match results.first() {
+                Some(ParseResult::Ok {
+                    data: Elements::Standard { element, .. },
+                    ..
+                }) => element,
+                _ => panic!("Standard element expected!"),
+            };
+
+            assert_eq!(element.args.len(), 2);
+            assert_eq!(element.args[0].val, "<a|b c>");
+            assert_eq!(element.args[1].val, "d");
+        }
+    }
+
+    #[test]
+    fn escaped_end() {
+        let square = Literal::new(b'[', b']').unwrap().with_escape(b'\\');
+        let content = "x a=[a\\]b] [c\\\\] [d\\e] f";
+
+        let results = YesDocParser::from_string(content, Some(vec![square.clone()]));
+        assert_eq!(results.len(), 1);
+
+        match results[0].data() {
+            Some(Elements::Standard { element, .. }) => {
+                let vals: Vec<&str> = element.args.iter().map(|kv| kv.val.as_str()).collect();
+                assert_eq!(vals, vec!["[a]b]", "[c\\]", "[d\\e]", "f"]);
+            }
+            _ => panic!("Standard element expected!"),
+        }
+
+        // Without an escape the first end byte closes the span.
+        let content = "x [a\\] b]";
+        for (literal, expected) in [
+            (square, vec!["[a] b]"]),
+            (Literal::new(b'[', b']').unwrap(), vec!["[a\\]", "b]"]),
+        ] {
+            let results = YesDocParser::from_string(content, Some(vec![literal]));
+            match results[0].data() {
+                Some(Elements::Standard { element, .. }) => {
+                    let vals: Vec<&str> = element.args.iter().map(|kv| kv.val.as_str()).collect();
+                    assert_eq!(vals, expected);
+                }
+                _ => panic!("Standard element expected!"),
+            }
+        }
     }
 }
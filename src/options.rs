@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+
+use crate::enums::{ControlChars, Delimiters, ErrorPlacement, GlyphSet};
+
+/// [ParserOptions] configure optional parsing behavior beyond the YES spec.
+/// The [Default] options parse documents exactly as the spec describes.
+///
+/// See [crate::YesDocParser::from_string_with_options] and
+/// [crate::YesDocParser::from_file_with_options].
+#[derive(Clone)]
+pub struct ParserOptions {
+    /// When true, ambiguous tokens are rejected instead of parsed leniently.
+    ///
+    /// - A value which begins with an unquoted [crate::enums::Glyphs::Equal]
+    ///   such as `a==b` reports [crate::enums::ErrorCodes::BadTokenPosEqual].
+    ///   Otherwise the value retains the second equal glyph, `=b`.
+    /// - A token which begins with [crate::enums::Glyphs::Equal] such as `==`
+    ///   has no key and reports [crate::enums::ErrorCodes::BadTokenPosEqual].
+    ///   Otherwise the token is skipped.
+    /// - A key which is assigned twice such as `a=1 a=2` reports
+    ///   [crate::enums::ErrorCodes::DuplicateKey]. Otherwise the last value wins.
+    /// - A literal span which is never terminated is an error even if
+    ///   [ParserOptions::lenient_literals] is true.
+    ///
+    /// A key with an empty value such as `a=` is valid in both modes.
+    pub strict: bool,
+
+    /// When true, a literal span which is never terminated continues to the
+    /// end of the line. Otherwise, which is the default, a quote such as
+    /// `name="abc` reports [crate::enums::ErrorCodes::UnterminatedQuote] and a
+    /// custom [crate::literal::Literal] span such as `a=[b c` reports
+    /// [crate::enums::ErrorCodes::UnbalancedLiteral].
+    pub lenient_literals: bool,
+
+    /// Names of elements whose arguments are captured raw. For these elements
+    /// the remainder of the line after the name is stored verbatim as one
+    /// nameless [crate::keyval::KeyVal]. Delimiters, literals, and quotes are
+    /// not processed, so `exec rm -rf "my dir"` has the value `rm -rf "my dir"`.
+    /// The element name itself is parsed as usual, so this also serves as a
+    /// rest-of-line capture: `title My Great App, v2` has the single value
+    /// `My Great App, v2`. Defaults to empty.
+    pub raw_elements: Vec<String>,
+
+    /// Keys whose value is captured raw. When one of these keys is followed
+    /// by [crate::enums::Glyphs::Equal], the remainder of the line is stored
+    /// verbatim as its value and no more tokens are collected, so
+    /// `run cmd=rm -rf /tmp label=cleanup` with `cmd` has one arg whose value
+    /// is `rm -rf /tmp label=cleanup`. The tokens before the key are parsed
+    /// as usual and the raw value does not decide their delimiter, so a comma
+    /// in `run label=x cmd=echo a,b` is kept in the value. Defaults to empty.
+    pub raw_keys: Vec<String>,
+
+    /// The maximum number of arguments kept for the named elements, e.g.
+    /// `lang` mapped to 1 keeps only `en` from `lang en fr`. Tokens after the
+    /// limit are not evaluated. In [ParserOptions::strict] mode the line
+    /// reports [crate::enums::ErrorCodes::TooManyArgs] instead. Elements which
+    /// are not named have no limit.
+    pub max_args: HashMap<String, usize>,
+
+    /// When [Some], the maximum number of arguments kept for any one line so
+    /// that a line with millions of tokens from an untrusted source cannot
+    /// grow without bound. Tokens after the limit are not collected and the
+    /// line is reported by [crate::document::Document::lint] as
+    /// [crate::lint::Lint::TruncatedArgs]. Defaults to [None] for no limit.
+    pub max_args_per_line: Option<usize>,
+
+    /// When true, the whitespace inside a key or value which is one literal
+    /// span is trimmed, so `name="  x  "` has the value `x` and
+    /// `list=[ 1 , 2 ]` has the value `[1 , 2]`. When false, which is the
+    /// default, the whitespace inside every kind of literal is preserved.
+    pub trim_literals: bool,
+
+    /// When true, the leading whitespace of each line is measured and
+    /// recorded as the `depth` of its [crate::ParseResult::Ok] so that
+    /// indentation can be used as structure. When false, which is the default,
+    /// indentation is ignored and the depth is [None].
+    pub indent_sensitive: bool,
+
+    /// The number of spaces one tab character counts as when
+    /// [ParserOptions::indent_sensitive] is true. Defaults to 4.
+    pub tab_width: usize,
+
+    /// When [Some], a line which is exactly this marker, such as `__END__`,
+    /// stops parsing. Every line after the marker is kept verbatim as the
+    /// trailing content of the document. See [crate::document::Document::trailing].
+    pub end_marker: Option<String>,
+
+    /// When true, [crate::enums::Glyphs::Hash] only begins a comment in the
+    /// first column of a line. An indented hash such as `  # x` is read as
+    /// data instead, here a standard element named `#` with the arg `x`.
+    /// Defaults to false and a comment may be indented.
+    pub comment_requires_column_zero: bool,
+
+    /// When true, consecutive comments directly before a standard element are
+    /// attached to it as [crate::element::Element::doc] instead of being
+    /// returned as standalone comments. Attributes may appear between the
+    /// comments and the element. A blank line, an error, or any other element
+    /// breaks the association and those comments are returned as usual.
+    pub doc_comments: bool,
+
+    /// When [Some], a logical line longer than this many bytes, not counting
+    /// surrounding whitespace, reports [crate::enums::ErrorCodes::LineTooLong].
+    pub max_line_length: Option<usize>,
+
+    /// The glyphs which may begin an element as a prefix. A prefix glyph
+    /// removed from this set is read as part of the element name instead, so
+    /// `GlyphSet::RESERVED.without(b'#')` parses `#tag` as a standard element.
+    /// This set is also checked by [ParserOptions::strict_reserved_in_values].
+    /// Defaults to [GlyphSet::RESERVED].
+    pub reserved: GlyphSet,
+
+    /// When [Some], a key which ends with this glyph directly before the
+    /// [crate::enums::Glyphs::Equal] glyph appends its value instead of
+    /// replacing it. With `Some('+')`, `tags+=a tags+=b` stores both values
+    /// under the key `tags`. See [crate::element::Element::get_key_value_vec].
+    /// Defaults to [None] and the glyph is kept as part of the key.
+    pub append_glyph: Option<char>,
+
+    /// When true, a comma or space preceded by a [crate::enums::Glyphs::Backslash]
+    /// outside of a literal span is part of the token instead of delimiting, so
+    /// `a=1\,2, b=3` has the value `1,2` and `tag a\ b` has the value `a b`.
+    /// Quoted values are unaffected. Defaults to false.
+    pub escape_delimiter: bool,
+
+    /// When true, a value which is not quoted and contains a glyph of
+    /// [ParserOptions::reserved] such as `!` in `note type=x!y` reports
+    /// [crate::enums::ErrorCodes::ReservedInValue]. Otherwise the glyph is
+    /// kept in the value. Defaults to false.
+    pub strict_reserved_in_values: bool,
+
+    /// When true, a line continued with a trailing
+    /// [crate::enums::Glyphs::Backslash] while a quote is open keeps the line
+    /// break, so a quoted value can span multiple lines as a paragraph.
+    /// Outside of a quote the lines are joined with nothing as usual.
+    /// Defaults to false.
+    pub quoted_newlines: bool,
+
+    /// When true, a [crate::enums::Glyphs::Backslash] inside of quotes
+    /// escapes the next character, so a quoted value can contain a quote:
+    /// `"a\"b"` has the value `a"b`. `\n` is a newline, `\t` is a tab, and
+    /// `\\` is a backslash. A quote whose end is escaped such as `"a\"`
+    /// reports [crate::enums::ErrorCodes::UnterminatedQuote] even if
+    /// [ParserOptions::lenient_literals] is true. Defaults to false and a backslash
+    /// inside of quotes is kept as-is. See [crate::utils::str_unescape].
+    pub quote_escapes: bool,
+
+    /// Decides what happens to a line containing a control character such as
+    /// a NUL byte. [ControlChars::Strip] removes them and
+    /// [ControlChars::Reject] reports
+    /// [crate::enums::ErrorCodes::ControlCharacter]. Defaults to
+    /// [ControlChars::Keep] which parses them like any other character.
+    pub control_chars: ControlChars,
+
+    /// When true, a line which is empty or only whitespace is read as
+    /// [crate::enums::Elements::Blank] so that tools can keep the vertical
+    /// spacing of a document. Otherwise, which is the default, it reports
+    /// [crate::enums::ErrorCodes::EolNoData].
+    pub blank_lines: bool,
+
+    /// Decides where errors are placed after globals are hoisted to the front
+    /// of the results. Defaults to [ErrorPlacement::Interleaved] which sorts
+    /// errors by line among the other non-global results.
+    pub error_placement: ErrorPlacement,
+
+    /// When true, an element prefix after another such as the bang of
+    /// `@!foo x=1` does not report [crate::enums::ErrorCodes::BadTokenPosBang]
+    /// or [crate::enums::ErrorCodes::BadTokenPosAttribute]. The first prefix
+    /// is kept and the rest is read as the name, so `@!foo x=1` is an
+    /// attribute named `!foo` with `x=1`. The line is reported by
+    /// [crate::document::Document::lint] as [crate::lint::Lint::RecoveredPrefix].
+    /// Defaults to false.
+    pub recover_prefixes: bool,
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        ParserOptions {
+            strict: false,
+            lenient_literals: false,
+            raw_elements: Vec::new(),
+            raw_keys: Vec::new(),
+            max_args: HashMap::new(),
+            max_args_per_line: None,
+            trim_literals: false,
+            indent_sensitive: false,
+            tab_width: 4,
+            end_marker: None,
+            comment_requires_column_zero: false,
+            doc_comments: false,
+            max_line_length: None,
+            reserved: GlyphSet::RESERVED,
+            append_glyph: None,
+            escape_delimiter: false,
+            strict_reserved_in_values: false,
+            quoted_newlines: false,
+            quote_escapes: false,
+            control_chars: ControlChars::Keep,
+            blank_lines: false,
+            error_placement: ErrorPlacement::Interleaved,
+            recover_prefixes: false,
+        }
+    }
+}
+
+/// [FormatOptions] configure how [crate::document::Document::format] renders a
+/// document back into YES source.
+#[derive(Clone)]
+pub struct FormatOptions {
+    /// The number of spaces for each level of nesting. Nesting is only known
+    /// for documents parsed with [ParserOptions::indent_sensitive]. Defaults
+    /// to 4.
+    pub indent: usize,
+
+    /// The delimiter placed between keyvals. [Delimiters::Unset] is treated
+    /// as [Delimiters::Space], which is the default.
+    pub delimiter: Delimiters,
+
+    /// When true, the names and keyvals of consecutive standard elements at
+    /// the same level are padded into columns so that their equal glyphs line
+    /// up. The comma delimiter is always used so that the padding can be
+    /// parsed again. Defaults to false.
+    pub align_equals: bool,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions {
+            indent: 4,
+            delimiter: Delimiters::Space,
+            align_equals: false,
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! Parses YES documents from an async reader as lines arrive. Requires the
+//! `async` feature. See [YesDocParser::from_async_reader].
+use futures::{
+    io::AsyncBufRead,
+    stream::{self, Stream},
+    AsyncBufReadExt, StreamExt,
+};
+
+use crate::{literal::Literal, options::ParserOptions, ParseResult, YesDocParser};
+
+impl YesDocParser {
+    /// Returns a [Stream] of [ParseResult] values read from [reader] as each
+    /// line arrives.
+    ///
+    /// Lines are fed through the same core as [YesDocParser::from_string] so
+    /// multi-lines, attributes, and doc comments behave the same. Unlike the
+    /// blocking readers, results are yielded in the order they are read and
+    /// [Elements::Global] elements are not hoisted to the front. Reading stops
+    /// at [ParserOptions::end_marker] or after the first I/O error, which is
+    /// yielded as a [ParseResult::Err].
+    ///
+    /// [Elements::Global]: crate::enums::Elements::Global
+    pub fn from_async_reader<R: AsyncBufRead + Unpin>(
+        reader: R,
+        literals: Option<Vec<Literal>>,
+    ) -> impl Stream<Item = ParseResult> {
+        YesDocParser::from_async_reader_with_options(reader, literals, ParserOptions::default())
+    }
+
+    /// A variation of [YesDocParser::from_async_reader] which parses with [options].
+    pub fn from_async_reader_with_options<R: AsyncBufRead + Unpin>(
+        reader: R,
+        literals: Option<Vec<Literal>>,
+        options: ParserOptions,
+    ) -> impl Stream<Item = ParseResult> {
+        let parser = YesDocParser::new(options);
+        let literals = YesDocParser::with_quotes(literals);
+        let state = Some((parser, literals, reader.lines()));
+
+        // Each step reads one line and yields every result it completed.
+        stream::unfold(state, |state| async move {
+            let (mut parser, literals, mut lines) = state?;
+
+            let finished = parser.step(lines.next().await, &literals);
+
+            let results = std::mem::take(&mut parser.results);
+            let state = (!finished).then_some((parser, literals, lines));
+            Some((results, state))
+        })
+        .flat_map(stream::iter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{executor::block_on, io::Cursor, StreamExt};
+
+    use crate::{enums::Elements, options::ParserOptions, ParseResult, YesDocParser};
+
+    #[test]
+    fn from_async_reader() {
+        let content = "# Doc for window\nwindow width=320 \\\n  height=240\n@!bad\n!version 2\n__END__\nignored";
+
+        let options = ParserOptions {
+            doc_comments: true,
+            end_marker: Some("__END__".to_owned()),
+            ..Default::default()
+        };
+
+        let stream =
+            YesDocParser::from_async_reader_with_options(Cursor::new(content), None, options);
+        let results: Vec<ParseResult> = block_on(stream.collect());
+
+        assert_eq!(results.len(), 3);
+
+        match results[0].data() {
+            Some(Elements::Standard { element, .. }) => {
+                assert_eq!(element.text, "window");
+                assert_eq!(element.doc, vec![" Doc for window"]);
+                assert_eq!(element.get_key_value("height"), Some("240".to_owned()));
+            }
+            _ => panic!("Standard element expected!"),
+        }
+
+        // Globals are yielded in order instead of hoisted.
+        assert!(results[1].is_err());
+        assert_eq!(results[1].line_number(), 4);
+
+        match results[2].data() {
+            Some(Elements::Global(element)) => assert_eq!(element.text, "version"),
+            _ => panic!("Global element expected!"),
+        }
+    }
+
+    #[test]
+    fn from_async_reader_matches_sync() {
+        let content = "!version 1\n@a\nb c=1\n# note\nd \\\ne";
+
+        let sync = YesDocParser::from_string(content, None);
+        let stream = YesDocParser::from_async_reader(Cursor::new(content), None);
+        let mut results: Vec<ParseResult> = block_on(stream.collect());
+
+        results.sort_by_key(ParseResult::line_number);
+        assert_eq!(results.len(), sync.len());
+
+        for (a, b) in results.iter().zip(sync.iter()) {
+            assert_eq!(a.line_number(), b.line_number());
+            assert_eq!(
+                a.data().map(ToString::to_string),
+                b.data().map(ToString::to_string)
+            );
+        }
+    }
+}
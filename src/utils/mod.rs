@@ -1,4 +1,133 @@
-use crate::enums::Glyphs;
+use std::borrow::Cow;
+
+use crate::enums::{Delimiters, Glyphs};
+
+/// If [s] is surrounded by quotes "", then this returns an owned copy of [s]
+/// without the quote characters at the front and back. Otherwise [s] is
+/// borrowed as-is and no allocation is made.
+/// See [StringUtils::unquote] for the in-place variant.
+pub fn str_unquote(s: &str) -> Cow<'_, str> {
+    let c = Glyphs::Quote.value();
+    let b = s.as_bytes();
+
+    if b.len() > 1 && b[0] == c && b[b.len() - 1] == c {
+        return Cow::Owned(s[1..s.len() - 1].to_owned());
+    }
+
+    Cow::Borrowed(s)
+}
+
+/// Returns [s] surrounded by quotes "" so that it is read back as one key or
+/// value. A quote or [Glyphs::Backslash] inside [s] is escaped with a
+/// backslash, the inverse of [str_unescape], so it is read back unchanged
+/// with [crate::options::ParserOptions::quote_escapes]. Each newline inside
+/// [s] is preceded by a backslash which continues the line, so it is kept when
+/// read with [crate::options::ParserOptions::quoted_newlines].
+/// See [str_unquote].
+pub fn str_quote(s: &str) -> String {
+    let quote = Glyphs::Quote.value() as char;
+    let backslash = Glyphs::Backslash.value() as char;
+    let mut out = String::with_capacity(s.len() + 2);
+
+    out.push(quote);
+    for c in s.chars() {
+        if c == quote || c == backslash || c == '\n' {
+            out.push(backslash);
+        }
+        out.push(c);
+    }
+    out.push(quote);
+
+    out
+}
+
+/// Decodes the escape sequences of the text inside of a pair of quotes:
+/// `\"` is a quote, `\\` is a backslash, `\n` is a newline, and `\t` is a
+/// tab. Any other escaped character is kept as-is with its backslash. If [s]
+/// has no backslash, then it is borrowed and no allocation is made.
+/// See [crate::options::ParserOptions::quote_escapes].
+pub fn str_unescape(s: &str) -> Cow<'_, str> {
+    let backslash = Glyphs::Backslash.value() as char;
+
+    if !s.contains(backslash) {
+        return Cow::Borrowed(s);
+    }
+
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        if c != backslash {
+            out.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some(next) if next == backslash || next == Glyphs::Quote.value() as char => {
+                out.push(next)
+            }
+            Some(next) => {
+                out.push(c);
+                out.push(next);
+            }
+            None => out.push(c),
+        }
+    }
+
+    Cow::Owned(out)
+}
+
+/// Returns true if [c] is whitespace per [Glyphs::is_whitespace]. This is
+/// the pattern used to trim keys, values, and names, e.g. `s.trim_matches(is_whitespace_char)`.
+pub(crate) fn is_whitespace_char(c: char) -> bool {
+    u8::try_from(c).is_ok_and(Glyphs::is_whitespace)
+}
+
+/// Returns true if [s] must be quoted to be written as one key or value and
+/// parsed back unchanged. That is the case if [s] contains the [delimiter] or
+/// any reserved glyph such as `=` or `,`, begins or ends with whitespace, or
+/// is itself surrounded by quotes. A newline, which cannot be written outside
+/// of quotes, always needs quoting. [Delimiters::Unset] is treated like
+/// [Delimiters::Space].
+///
+/// Glyphs between a pair of quotes inside [s] are already kept together by
+/// the parser, so they do not count. Adding quotes around such a value would
+/// split it apart instead. An unbalanced quote always needs quoting.
+pub fn needs_quoting(s: &str, delimiter: Delimiters) -> bool {
+    let delimiter = match delimiter {
+        Delimiters::Unset => Delimiters::Space,
+        delimiter => delimiter,
+    };
+
+    if s.starts_with(char::is_whitespace) || s.ends_with(char::is_whitespace) || s.contains('\n') {
+        return true;
+    }
+
+    if s.len() > 1 && String::from(s).is_quoted() {
+        return true;
+    }
+
+    let quote = Glyphs::Quote.value();
+    let mut in_quote = false;
+    let bare = s.bytes().any(|c| {
+        if c == quote {
+            in_quote = !in_quote;
+            return false;
+        }
+
+        let is_delim = match delimiter {
+            Delimiters::Space => Glyphs::is_whitespace(c),
+            _ => c == delimiter.value(),
+        };
+
+        !in_quote && (is_delim || Glyphs::is_reserved(c))
+    });
+
+    // An unbalanced quote would keep the next delimiter in this span.
+    bare || in_quote
+}
 
 /// Common [String] utils that are used to simplify parsing.
 pub trait StringUtils {
@@ -64,7 +193,7 @@ impl StringUtils for String {
 
         let mut substr = None;
         for (i, c) in b {
-            if c != Glyphs::Space.value() as u8 {
+            if !Glyphs::is_whitespace(c) {
                 substr = Some(self.substring(i, self.len() - i));
                 break;
             }
@@ -87,7 +216,7 @@ impl StringUtils for String {
 
         let mut substr = None;
         for (i, c) in b {
-            if c != Glyphs::Space.value() as u8 {
+            if !Glyphs::is_whitespace(c) {
                 substr = Some(self.substring(0, i + 1));
                 break;
             }
@@ -112,13 +241,18 @@ impl StringUtils for String {
 
 #[cfg(test)]
 mod tests {
-    use crate::utils::StringUtils;
+    use std::borrow::Cow;
+
+    use crate::{
+        enums::Delimiters,
+        utils::{needs_quoting, str_quote, str_unescape, str_unquote, StringUtils},
+    };
 
     #[test]
     fn is_quoted() {
         let hwq = "\"Hello, world!\"";
         let str: String = hwq.to_owned();
-        assert_eq!(str.is_quoted(), true);
+        assert!(str.is_quoted());
     }
 
     #[test]
@@ -151,5 +285,64 @@ mod tests {
         let mut str = hw.to_owned();
         assert_eq!(str.trim(), hw);
         assert_eq!(padded_hw.trim(), hw);
+
+        let mut tabbed_hw = "\t\t\x0B Hello, world!\x0C\t".to_owned();
+        assert_eq!(tabbed_hw.trim(), hw);
+    }
+
+    #[test]
+    fn str_unquote_borrows() {
+        assert!(matches!(str_unquote("Hello"), Cow::Borrowed("Hello")));
+        assert!(matches!(str_unquote("\"Hello"), Cow::Borrowed("\"Hello")));
+        assert!(matches!(str_unquote("\""), Cow::Borrowed("\"")));
+        assert_eq!(str_unquote("\"Hello\""), "Hello");
+        assert_eq!(str_unquote("\"\""), "");
+    }
+
+    #[test]
+    fn needs_quoting_test() {
+        assert!(!needs_quoting("hello", Delimiters::Space));
+        assert!(!needs_quoting("", Delimiters::Space));
+
+        // Commas are reserved under either delimiter.
+        assert!(needs_quoting("a,b", Delimiters::Space));
+        assert!(needs_quoting("a,b", Delimiters::Comma));
+
+        // Spaces only delimit under the space delimiter.
+        assert!(needs_quoting("a b", Delimiters::Space));
+        assert!(needs_quoting("a b", Delimiters::Unset));
+        assert!(!needs_quoting("a b", Delimiters::Comma));
+
+        // Surrounding whitespace would be trimmed.
+        assert!(needs_quoting(" a", Delimiters::Comma));
+        assert!(needs_quoting("a\t", Delimiters::Comma));
+
+        assert!(needs_quoting("a=b", Delimiters::Comma));
+        assert!(needs_quoting("x!y", Delimiters::Comma));
+
+        // Glyphs inside a pair of quotes are kept together when parsed.
+        assert!(!needs_quoting("b\"a,b\"", Delimiters::Space));
+        assert!(!needs_quoting("a\"b c\"d", Delimiters::Space));
+        assert!(needs_quoting("a\"b c\"d=", Delimiters::Space));
+
+        // Values surrounded by quotes or with an unbalanced quote are not.
+        assert!(needs_quoting("\"a\"", Delimiters::Space));
+        assert!(needs_quoting("a\"b", Delimiters::Space));
+
+        // A newline can only be written inside of quotes.
+        assert!(needs_quoting("a\nb", Delimiters::Comma));
+    }
+
+    #[test]
+    fn str_quote_test() {
+        assert_eq!(str_quote("a b"), "\"a b\"");
+        assert_eq!(str_quote(""), "\"\"");
+        assert_eq!(str_quote("a\nb"), "\"a\\\nb\"");
+
+        // Quotes and backslashes are escaped as they are unescaped.
+        for s in ["a\"b", "a\\", "\\\"", "C:\\a b"] {
+            let quoted = str_quote(s);
+            assert_eq!(str_unescape(&quoted[1..quoted.len() - 1]), s);
+        }
     }
 }
@@ -0,0 +1,26 @@
+use crate::{element::Element, enums::ErrorCodes};
+
+/// [Visitor] reacts to each kind of parsed element while walking a document
+/// with [crate::document::Document::accept]. Every method does nothing by
+/// default so that implementations only override the kinds they care about.
+pub trait Visitor {
+    /// Called for every [crate::enums::Elements::Standard] element. [attrs] are
+    /// the attributes applied to [element], which are each visited with
+    /// [Visitor::visit_attribute] first.
+    fn visit_standard(&mut self, _line_number: usize, _element: &Element, _attrs: &[Element]) {}
+
+    /// Called for every attribute in the order they were declared.
+    fn visit_attribute(&mut self, _line_number: usize, _element: &Element) {}
+
+    /// Called for every [crate::enums::Elements::Global] element.
+    fn visit_global(&mut self, _line_number: usize, _element: &Element) {}
+
+    /// Called for every [crate::enums::Elements::Comment] element.
+    fn visit_comment(&mut self, _line_number: usize, _element: &Element) {}
+
+    /// Called for every [crate::enums::Elements::Blank] line.
+    fn visit_blank(&mut self, _line_number: usize) {}
+
+    /// Called for every [crate::ParseResult::Err] result.
+    fn visit_error(&mut self, _line_number: usize, _code: &ErrorCodes, _message: &str) {}
+}
@@ -0,0 +1,55 @@
+//! Counts heap allocations made by the parser. This lives in its own test
+//! target because it must install a global allocator.
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use yes_parser::{utils::str_unquote, YesDocParser};
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn count<F: FnOnce() -> R, R>(f: F) -> usize {
+    let before = ALLOCATIONS.load(Ordering::SeqCst);
+    let result = f();
+    let after = ALLOCATIONS.load(Ordering::SeqCst);
+    drop(result);
+    after - before
+}
+
+#[test]
+fn unquote_allocations() {
+    // Unquoted values are borrowed.
+    assert_eq!(count(|| str_unquote("value")), 0);
+
+    // Quoted values allocate exactly once.
+    assert_eq!(count(|| str_unquote("\"value\"")), 1);
+
+    // Unquoting is free once the key or value is owned by its [KeyVal],
+    // so an all-unquoted document allocates as much as the same document
+    // whose keys and values are all quoted.
+    let unquoted = "frame duration=1.0s width=10 height=20\n\
+        frame duration=2.0s width=30 height=40";
+    let quoted = "frame \"duration\"=\"1.0s\" \"width\"=\"10\" \"height\"=\"20\"\n\
+        frame \"duration\"=\"2.0s\" \"width\"=\"30\" \"height\"=\"40\"";
+
+    let a = count(|| YesDocParser::from_string(unquoted, None));
+    let b = count(|| YesDocParser::from_string(quoted, None));
+    assert_eq!(a, b);
+}
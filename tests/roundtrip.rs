@@ -0,0 +1,313 @@
+//! Property test asserting that parsing is stable across a round-trip through
+//! [Document::format]: for any generated source, `parse -> format -> parse`
+//! must yield the same elements as the first parse, and formatting that
+//! second parse must return the same source again.
+//!
+//! Documents are generated from a fixed seed so that every failure can be
+//! reproduced. Set `YES_ROUNDTRIP_CASES` to run more cases locally.
+//!
+//! Formatting normalizes the following, which is not compared:
+//! - line numbers, since attributes and doc comments move above their element
+//! - whitespace around names, keys, values, and delimiters
+//! - trailing blank lines
+//!
+//! With [ParserOptions::doc_comments], a comment directly above a global may
+//! become the doc comment of the next standard element once the global is
+//! hoisted, so that case is generated without globals.
+//!
+//! Quoted keys and values are written with their quote and backslash glyphs
+//! escaped, which only [ParserOptions::quote_escapes] reads back. Without it,
+//! a key or value which contains either glyph and also needs quoting cannot be
+//! written back, and documents with such keys or values are not generated.
+use yes_parser::{
+    document::Document,
+    element::Element,
+    enums::{Delimiters, Elements, ErrorCodes},
+    options::{FormatOptions, ParserOptions},
+    utils::needs_quoting,
+    ParseResult,
+};
+
+/// The pieces that generated lines are built from. They are chosen to hit
+/// every glyph with a meaning in the spec, quoting, and multibyte text.
+const PIECES: &[&str] = &[
+    "a", "b", "key", "val", "x1", "10", "-2.5", "é", "名前", " ", "  ", "\t", "=", ",", "@", "!",
+    "#", "@@", "a b", "\"a b\"", "\"a,b\"", "\"a=b\"", "\"\"", "==", ", ",
+];
+
+/// Pieces which continue a quote onto the next line or escape a quote inside
+/// of one. See [ParserOptions::quoted_newlines] and [ParserOptions::quote_escapes].
+const ESCAPED_PIECES: &[&str] = &["\"a \\\nb\"", "\"a\\\"b\""];
+
+/// A small xorshift generator so that the test needs no dependencies.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+fn gen_line(rng: &mut Rng, globals: bool) -> String {
+    let mut line = String::new();
+
+    match rng.below(8) {
+        0 => line.push('@'),
+        1 if globals => line.push('!'),
+        2 => line.push('#'),
+        _ => {}
+    }
+
+    for _ in 0..rng.below(8) {
+        let i = rng.below(PIECES.len() + ESCAPED_PIECES.len());
+        line += PIECES
+            .get(i)
+            .unwrap_or_else(|| &ESCAPED_PIECES[i - PIECES.len()]);
+    }
+
+    // A piece may also begin the line with the global prefix.
+    if !globals && line.trim_start().starts_with('!') {
+        line.insert(0, 'a');
+    }
+
+    line
+}
+
+/// Returns a line from [gen_line] which can be written back. The line is
+/// followed by a standard element so that an attribute is checked too.
+fn gen_representable_line(rng: &mut Rng, globals: bool, options: &ParserOptions) -> String {
+    loop {
+        let line = gen_line(rng, globals);
+        let doc =
+            Document::from_string_with_options(&format!("{}\nz", line), None, options.clone());
+
+        if representable_doc(&doc, options) {
+            return line;
+        }
+    }
+}
+
+fn gen_document(rng: &mut Rng, globals: bool, options: &ParserOptions) -> String {
+    (0..1 + rng.below(8))
+        .map(|_| gen_representable_line(rng, globals, options))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Describes an [Element] without its position in the source.
+fn describe_element(element: &Element) -> String {
+    let args: Vec<String> = element
+        .args
+        .iter()
+        .map(|kv| format!("{:?}={:?}", kv.key, kv.val))
+        .collect();
+
+    format!(
+        "{:?} [{}] doc={:?}",
+        element.text,
+        args.join(" "),
+        element.doc
+    )
+}
+
+/// Describes every result without its position in the source.
+fn describe(doc: &Document) -> Vec<String> {
+    doc.results
+        .iter()
+        .map(|result| match result {
+            ParseResult::Err { code, .. } => format!("error {:?}", code),
+            ParseResult::Ok { data, .. } => match data {
+                Elements::Standard { attrs, element } => {
+                    let attrs: Vec<String> = attrs.iter().map(describe_element).collect();
+                    format!("standard {} attrs={:?}", describe_element(element), attrs)
+                }
+                Elements::Attribute(element) => format!("attribute {}", describe_element(element)),
+                Elements::Global(element) => format!("global {}", describe_element(element)),
+                Elements::Comment(element) => format!("comment {:?}", element.text),
+                Elements::Blank => "blank".to_owned(),
+            },
+        })
+        .collect()
+}
+
+/// Returns true if the name and every key and value of [element] can be
+/// written back when parsed with [options].
+fn representable(element: &Element, options: &ParserOptions) -> bool {
+    // Names are never escaped, so a quote inside of one can end an escaped
+    // quote of the same line early.
+    let name_fits = |s: &str| {
+        !s.contains('"') || (!options.quote_escapes && !needs_quoting(s, Delimiters::Space))
+    };
+    let fits = |s: &str| {
+        let escaped = s.contains('"') || s.contains('\\');
+        (options.quote_escapes || !escaped || !needs_quoting(s, Delimiters::Space))
+            && (options.quoted_newlines || !s.contains('\n'))
+    };
+
+    name_fits(&element.text)
+        && element
+            .args
+            .iter()
+            .all(|kv| kv.key.as_deref().is_none_or(fits) && fits(&kv.val))
+}
+
+/// Returns true if every element of [doc] can be written back.
+fn representable_doc(doc: &Document, options: &ParserOptions) -> bool {
+    doc.results.iter().all(|result| match result.data() {
+        Some(Elements::Standard { attrs, element }) => {
+            representable(element, options) && attrs.iter().all(|attr| representable(attr, options))
+        }
+        Some(Elements::Attribute(element)) | Some(Elements::Global(element)) => {
+            representable(element, options)
+        }
+        _ => true,
+    })
+}
+
+fn check(source: &str, options: &ParserOptions, format: &FormatOptions) {
+    let first = Document::from_string_with_options(source, None, options.clone());
+
+    if !representable_doc(&first, options) {
+        return;
+    }
+
+    let formatted = first.format(format.clone());
+
+    let second = Document::from_string_with_options(&formatted, None, options.clone());
+    let mut expected = describe(&first);
+    let mut actual = describe(&second);
+
+    // Trailing blank lines are dropped by formatting.
+    let blank = [
+        format!("error {:?}", ErrorCodes::EolNoData),
+        "blank".to_owned(),
+    ];
+    while expected.last().is_some_and(|last| blank.contains(last)) {
+        expected.pop();
+    }
+    while actual.last().is_some_and(|last| blank.contains(last)) {
+        actual.pop();
+    }
+
+    assert_eq!(
+        expected, actual,
+        "\nsource:\n{}\nformatted:\n{}",
+        source, formatted
+    );
+
+    assert_eq!(
+        second.format(format.clone()),
+        formatted,
+        "\nformatting is not stable for source:\n{}",
+        source
+    );
+}
+
+fn cases() -> usize {
+    std::env::var("YES_ROUNDTRIP_CASES")
+        .ok()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(2000)
+}
+
+#[test]
+fn roundtrip_default() {
+    let mut rng = Rng(0x5EED_1234_ABCD_0001);
+    let options = ParserOptions::default();
+
+    for _ in 0..cases() {
+        check(
+            &gen_document(&mut rng, true, &options),
+            &options,
+            &FormatOptions::default(),
+        );
+    }
+}
+
+#[test]
+fn roundtrip_comma_delimited() {
+    let mut rng = Rng(0x5EED_1234_ABCD_0002);
+    let options = ParserOptions::default();
+    let format = FormatOptions {
+        delimiter: Delimiters::Comma,
+        ..Default::default()
+    };
+
+    for _ in 0..cases() {
+        check(&gen_document(&mut rng, true, &options), &options, &format);
+    }
+}
+
+#[test]
+fn roundtrip_doc_comments() {
+    let mut rng = Rng(0x5EED_1234_ABCD_0004);
+    let options = ParserOptions {
+        doc_comments: true,
+        blank_lines: true,
+        ..Default::default()
+    };
+
+    for _ in 0..cases() {
+        check(
+            &gen_document(&mut rng, false, &options),
+            &options,
+            &FormatOptions::default(),
+        );
+    }
+}
+
+#[test]
+fn roundtrip_quoted_newlines() {
+    let mut rng = Rng(0x5EED_1234_ABCD_0005);
+    let options = ParserOptions {
+        quoted_newlines: true,
+        ..Default::default()
+    };
+
+    for _ in 0..cases() {
+        check(
+            &gen_document(&mut rng, true, &options),
+            &options,
+            &FormatOptions::default(),
+        );
+    }
+}
+
+#[test]
+fn roundtrip_quote_escapes() {
+    let mut rng = Rng(0x5EED_1234_ABCD_0006);
+    let options = ParserOptions {
+        quote_escapes: true,
+        quoted_newlines: true,
+        ..Default::default()
+    };
+
+    for _ in 0..cases() {
+        check(
+            &gen_document(&mut rng, true, &options),
+            &options,
+            &FormatOptions::default(),
+        );
+    }
+}
+
+#[test]
+fn roundtrip_aligned() {
+    let mut rng = Rng(0x5EED_1234_ABCD_0003);
+    let options = ParserOptions::default();
+    let format = FormatOptions {
+        align_equals: true,
+        ..Default::default()
+    };
+
+    for _ in 0..cases() {
+        check(&gen_document(&mut rng, true, &options), &options, &format);
+    }
+}